@@ -1,9 +1,347 @@
-use clap::Parser;
+use automc::distribution::{BuildToolsOptions, Compile, Distribution};
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser, Debug, Clone)]
 #[command(version, about, long_about = None)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Proxy for HTTP and HTTPS requests, overrides the config
+    #[arg(long, global = true)]
+    pub proxy: Option<String>,
+
+    /// Additional trusted CA certificate (PEM), can be repeated
+    #[arg(long = "ca-cert", global = true)]
+    pub ca_certs: Vec<PathBuf>,
+
+    /// Only use cached metadata and downloads, never the network
+    #[arg(long, global = true)]
+    pub offline: bool,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Install a new server (default)
+    Install(InstallArgs),
+    /// Update the server jar of an existing server
+    Update(UpdateArgs),
+    /// List registered servers
+    List,
+    /// Show information about a server
+    Info(InfoArgs),
+    /// List servers with newer builds or versions available
+    Outdated(OutdatedArgs),
+    /// List builds of a version with their publish time and changes
+    Builds(BuildsArgs),
+    /// Show the changes between the installed build and the one `update` would install
+    Changelog(ChangelogArgs),
+    /// Start a server using its start script
+    Run(RunArgs),
+    /// Create a zip archive of a server directory
+    Backup(BackupArgs),
+    /// Check Java installations, config and API connectivity
+    Doctor,
+    /// Manage the local download cache
+    Cache(CacheArgs),
+    /// Converge a server directory to its `automc.toml`
+    Apply(ApplyArgs),
+    /// Unregister a server, its files are kept
+    Remove(RemoveArgs),
+    /// Register a server that wasn't installed by automc
+    Adopt(AdoptArgs),
+}
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct InstallArgs {
     /// Supply custom Java path
+    #[arg(long, short, alias = "java")]
+    pub java_path: Option<String>,
+
+    /// Directory to install the server into
+    #[arg(long, short)]
+    pub dir: Option<PathBuf>,
+
+    /// Name to register the server as, defaults to the directory name
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Initial and maximum heap size of the server, e.g. `4G`
+    #[arg(long)]
+    pub memory: Option<String>,
+
+    /// Server distribution to install
+    #[arg(long, value_enum, ignore_case = true)]
+    pub distribution: Option<Distribution>,
+
+    /// Minecraft (or Velocity) version to install, or a constraint like `latest`,
+    /// `latest-snapshot`, `1.21.x` or `>=1.20.4,<1.21`
+    #[arg(long)]
+    pub version: Option<String>,
+
+    /// Build (loader version for Fabric) or `latest`, see `automc builds` for the available builds
+    #[arg(long, alias = "loader")]
+    pub build: Option<String>,
+
+    /// Release channel used to pick the latest build
+    #[arg(long)]
+    pub channel: Option<String>,
+
+    /// Installer version or `latest` (Fabric), defaults to the newest stable installer
+    #[arg(long)]
+    pub installer: Option<String>,
+
+    /// Include unstable versions and installers
+    #[arg(long)]
+    pub unstable: bool,
+
+    /// Accept the Minecraft EULA (https://www.minecraft.net/eula)
+    #[arg(long)]
+    pub accept_eula: bool,
+
+    /// Don't verify downloads against their published checksums
+    #[arg(long)]
+    pub skip_verify: bool,
+
+    /// Neither use nor fill the local download cache
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Keep the temporary BuildTools workspace of a Spigot build that didn't use the cached one
+    #[arg(long)]
+    pub keep_build_cache: bool,
+
+    #[command(flatten)]
+    pub build_tools: BuildToolsArgs,
+
+    /// Reinstall exactly what the `automc.lock` in the directory records
+    #[arg(long)]
+    pub locked: bool,
+}
+
+/// Options for BuildTools when building Spigot.
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct BuildToolsArgs {
+    /// Server BuildTools compiles
+    #[arg(long, value_enum)]
+    pub compile: Option<Compile>,
+
+    /// Also build the Mojang mapped jars for plugin development
+    #[arg(long)]
+    pub remapped: bool,
+
+    /// File name BuildTools gives the built jar
+    #[arg(long)]
+    pub final_name: Option<String>,
+
+    /// Stop BuildTools if it runs longer than this many minutes
+    #[arg(long, value_name = "MINUTES", value_parser = clap::value_parser!(u64).range(1..))]
+    pub build_timeout: Option<u64>,
+}
+
+impl BuildToolsArgs {
+    /// Whether any option was passed on the command line.
+    pub fn is_set(&self) -> bool {
+        self.compile.is_some() || self.remapped || self.final_name.is_some()
+    }
+
+    /// The options passed on the command line, or if there are none, the `recorded` ones with
+    /// the timeout from the command line.
+    pub fn options_or(&self, recorded: Option<&BuildToolsOptions>) -> BuildToolsOptions {
+        let options = BuildToolsOptions::from(self);
+        match recorded {
+            Some(recorded) if !self.is_set() => BuildToolsOptions {
+                timeout: options.timeout,
+                ..recorded.clone()
+            },
+            _ => options,
+        }
+    }
+}
+
+impl From<&BuildToolsArgs> for BuildToolsOptions {
+    fn from(args: &BuildToolsArgs) -> Self {
+        Self {
+            compile: args.compile,
+            remapped: args.remapped,
+            final_name: args.final_name.clone(),
+            timeout: args.build_timeout.map(|m| Duration::from_secs(m * 60)),
+        }
+    }
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct InfoArgs {
+    /// Server name or directory
+    #[arg(default_value = ".")]
+    pub dir: PathBuf,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct RunArgs {
+    /// Server name or directory
+    #[arg(default_value = ".")]
+    pub dir: PathBuf,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct BackupArgs {
+    /// Server name or directory
+    #[arg(default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Path of the created archive, defaults to `<dir name>-<unix time>.zip` in the current directory
     #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    pub command: CacheCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum CacheCommand {
+    /// List cached files
+    List,
+    /// Remove files that weren't used recently
+    Prune {
+        /// Remove files not used for this many days
+        #[arg(long, default_value_t = 30)]
+        older_than: u64,
+    },
+    /// Remove the whole cache
+    Clear,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ApplyArgs {
+    /// Server directory
+    #[arg(default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Spec file, defaults to `automc.toml` in the server directory
+    #[arg(long, short)]
+    pub file: Option<PathBuf>,
+
+    /// Neither use nor fill the local download cache
+    #[arg(long)]
+    pub no_cache: bool,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct UpdateArgs {
+    /// Server name or directory
+    #[arg(default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Version or constraint to update to, defaults to the installed version
+    #[arg(long)]
+    pub version: Option<String>,
+
+    /// Build to update to, defaults to the newest build on the installed build's channel
+    #[arg(long)]
+    pub build: Option<String>,
+
+    /// Release channel used to pick the newest build
+    #[arg(long)]
+    pub channel: Option<String>,
+
+    /// Java used to build Spigot, defaults to the one recorded in `automc.lock`
+    #[arg(long, short, alias = "java")]
+    pub java_path: Option<String>,
+
+    /// Don't verify downloads against their published checksums
+    #[arg(long)]
+    pub skip_verify: bool,
+
+    /// Neither use nor fill the local download cache
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Keep the temporary BuildTools workspace of a Spigot build that didn't use the cached one
+    #[arg(long)]
+    pub keep_build_cache: bool,
+
+    #[command(flatten)]
+    pub build_tools: BuildToolsArgs,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct OutdatedArgs {
+    /// Server names or directories, defaults to all registered servers
+    pub dirs: Vec<PathBuf>,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct BuildsArgs {
+    /// Server distribution
+    #[arg(value_enum, ignore_case = true)]
+    pub distribution: Distribution,
+
+    /// Version or constraint, e.g. `1.21.1` or `latest`
+    #[arg(default_value = "latest")]
+    pub version: String,
+
+    /// Only list builds of this release channel
+    #[arg(long)]
+    pub channel: Option<String>,
+
+    /// Number of builds to list, newest first
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+
+    /// Print the builds as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ChangelogArgs {
+    /// Server name or directory
+    #[arg(default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Version or constraint to compare with, defaults to the installed version
+    #[arg(long)]
+    pub version: Option<String>,
+
+    /// Build to compare with, defaults to the newest build on the installed build's channel
+    #[arg(long)]
+    pub build: Option<String>,
+
+    /// Release channel used to pick the newest build
+    #[arg(long)]
+    pub channel: Option<String>,
+
+    /// Print the builds and their changes as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct RemoveArgs {
+    /// Name of the server
+    pub name: String,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct AdoptArgs {
+    /// Server directory
+    pub dir: PathBuf,
+
+    /// Name to register the server as, defaults to the directory name
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Java used to run the server
+    #[arg(long, short, alias = "java")]
     pub java_path: Option<String>,
 }
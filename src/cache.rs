@@ -0,0 +1,373 @@
+//! Local cache of downloaded server jars, BuildTools and API responses.
+
+use crate::distribution::{Artifact, Checksum};
+use crate::error::*;
+use crate::http::Http;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::hash::{BuildHasher, RandomState};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+const ARTIFACTS_DIR: &str = "artifacts";
+const METADATA_DIR: &str = "metadata";
+const BUILDTOOLS_DIR: &str = "buildtools";
+
+/// Local cache of server jars and tools shared by all installs.
+///
+/// Artifacts are stored under `artifacts/<distribution>/<version>/<build>/` and, if the API
+/// publishes a checksum, in a sub directory named after it, so a republished build never
+/// reuses a stale file. Downloads with a published checksum are only added once verified,
+/// those without one (Fabric jars and BuildTools) as downloaded. Concurrent installs download
+/// into separate temporary files and the first one to finish becomes the entry.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/// A file in the artifact cache.
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    /// Path relative to the artifact cache, e.g. `paper/1.21.1/132/sha256-…/paper-1.21.1-132.jar`.
+    pub key: String,
+    /// Absolute path of the file.
+    pub path: PathBuf,
+    /// Size in bytes.
+    pub size: u64,
+    /// Updated every time the entry is used by an install.
+    pub last_used: SystemTime,
+}
+
+impl Cache {
+    /// Cache in `dir`, which is created when something is added.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `automc` in the platform's cache directory (e.g. `~/.cache/automc` on Linux).
+    pub fn default_dir() -> Result<PathBuf> {
+        ProjectDirs::from("rs", "", "automc")
+            .map(|dirs| dirs.cache_dir().to_path_buf())
+            .ok_or_else(|| Error::Other("no cache directory found".to_string()))
+    }
+
+    /// Root directory of the cache.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Cache of API responses in this cache, reused for `ttl`.
+    pub fn metadata(&self, ttl: Duration) -> MetadataCache {
+        MetadataCache {
+            dir: self.dir.join(METADATA_DIR),
+            ttl,
+        }
+    }
+
+    /// Persistent workspace BuildTools runs in, so its Git checkouts and Maven repository are
+    /// reused by later builds of any version.
+    pub fn build_tools_dir(&self) -> PathBuf {
+        self.dir.join(BUILDTOOLS_DIR)
+    }
+
+    /// Size of the BuildTools workspace, `None` if there is none.
+    pub fn build_tools_size(&self) -> Result<Option<u64>> {
+        let dir = self.build_tools_dir();
+        match dir.exists() {
+            true => Ok(Some(dir_size(&dir)?)),
+            false => Ok(None),
+        }
+    }
+
+    /// Key of a downloaded artifact.
+    pub fn artifact_key(artifact: &Artifact) -> Vec<String> {
+        let mut key = vec![
+            artifact.distribution.to_string().to_lowercase(),
+            artifact.version.clone(),
+            artifact.build.clone(),
+        ];
+        if let Some(checksum) = &artifact.checksum {
+            key.push(checksum_dir(checksum));
+        }
+        key.push(artifact.file_name.clone());
+        key
+    }
+
+    /// Path of `key` in the cache, whether it exists or not.
+    pub fn path(&self, key: &[String]) -> PathBuf {
+        let mut path = self.dir.join(ARTIFACTS_DIR);
+        for part in key {
+            // keys come from API responses, don't let them escape the cache
+            path.push(part.replace(['/', '\\'], "_").replace("..", "_"));
+        }
+        path
+    }
+
+    /// Path of the cached file for `key`, if there is one. Marks the entry as used.
+    pub fn get(&self, key: &[String]) -> Option<PathBuf> {
+        let path = self.path(key);
+        if !path.is_file() {
+            return None;
+        }
+
+        // the modification time tracks the last use for `prune`
+        let _ = File::options()
+            .append(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+
+        Some(path)
+    }
+
+    /// Copies `src` into the cache as `key`, unless another install added it meanwhile.
+    pub async fn insert(&self, key: &[String], src: &Path) -> Result<PathBuf> {
+        let path = self.path(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let part = copy_to_part(src, &path).await?;
+        persist(&part, &path).await?;
+
+        Ok(path)
+    }
+
+    /// Path of the cached file for `key`, downloading it from `url` first if it isn't cached.
+    pub async fn fetch(
+        &self,
+        http: &Http,
+        key: &[String],
+        url: &str,
+        checksum: Option<&Checksum>,
+        message: &str,
+    ) -> Result<PathBuf> {
+        if let Some(path) = self.get(key) {
+            println!("✔ Using cached {}", message);
+            return Ok(path);
+        }
+
+        let path = self.path(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let part = http.download_part(url, &path, checksum, message).await?;
+        persist(&part, &path).await?;
+
+        Ok(path)
+    }
+
+    /// All cached artifacts, sorted by key.
+    pub fn list(&self) -> Result<Vec<CacheEntry>> {
+        let root = self.dir.join(ARTIFACTS_DIR);
+        if !root.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        for entry in WalkDir::new(&root).sort_by_file_name() {
+            let entry = entry.map_err(io::Error::from)?;
+            if !entry.file_type().is_file() || is_partial(entry.path()) {
+                continue;
+            }
+
+            let meta = entry.metadata().map_err(io::Error::from)?;
+            let key = entry
+                .path()
+                .strip_prefix(&root)
+                .map_err(|e| Error::Other(e.to_string()))?
+                .to_string_lossy()
+                .replace('\\', "/");
+            entries.push(CacheEntry {
+                key,
+                path: entry.path().to_path_buf(),
+                size: meta.len(),
+                last_used: meta.modified()?,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// Removes artifacts that weren't used for `max_age` and leftovers of interrupted downloads.
+    /// Returns the removed entries.
+    pub fn prune(&self, max_age: Duration) -> Result<Vec<CacheEntry>> {
+        let root = self.dir.join(ARTIFACTS_DIR);
+        if !root.exists() {
+            return Ok(Vec::new());
+        }
+
+        for entry in WalkDir::new(&root) {
+            let entry = entry.map_err(io::Error::from)?;
+            if entry.file_type().is_file() && is_partial(entry.path()) {
+                fs::remove_file(entry.path())?;
+            }
+        }
+
+        let now = SystemTime::now();
+        let mut removed = Vec::new();
+        for entry in self.list()? {
+            let age = now.duration_since(entry.last_used).unwrap_or_default();
+            if age >= max_age {
+                fs::remove_file(&entry.path)?;
+                removed.push(entry);
+            }
+        }
+
+        remove_empty_dirs(&root)?;
+        Ok(removed)
+    }
+
+    /// Removes the whole cache. Returns the number of freed bytes.
+    pub fn clear(&self) -> Result<u64> {
+        if !self.dir.exists() {
+            return Ok(0);
+        }
+
+        let size = dir_size(&self.dir)?;
+        fs::remove_dir_all(&self.dir)?;
+
+        Ok(size)
+    }
+}
+
+/// Total size of the files in `dir`.
+fn dir_size(dir: &Path) -> Result<u64> {
+    let mut size = 0;
+    for entry in WalkDir::new(dir) {
+        let entry = entry.map_err(io::Error::from)?;
+        if entry.file_type().is_file() {
+            size += entry.metadata().map_err(io::Error::from)?.len();
+        }
+    }
+    Ok(size)
+}
+
+/// Cache of API responses, used by [`Http::get_text`](crate::http::Http::get_text).
+///
+/// Responses younger than the TTL are used without a request, older ones are revalidated with
+/// `If-None-Match`/`If-Modified-Since`.
+#[derive(Debug, Clone)]
+pub struct MetadataCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+/// A cached API response.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct CachedResponse {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Unix time of the last request that returned or confirmed `body`.
+    pub fetched_at: u64,
+    pub body: String,
+}
+
+impl MetadataCache {
+    fn path(&self, url: &str) -> PathBuf {
+        let hash = hex::encode(Sha256::digest(url.as_bytes()));
+        self.dir.join(format!("{}.json", hash))
+    }
+
+    pub(crate) fn load(&self, url: &str) -> Option<CachedResponse> {
+        let content = fs::read_to_string(self.path(url)).ok()?;
+        serde_json::from_str::<CachedResponse>(&content)
+            .ok()
+            .filter(|res| res.url == url)
+    }
+
+    pub(crate) fn store(&self, res: &CachedResponse) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        // write and rename so concurrent runs never read half a file
+        let path = self.path(&res.url);
+        let part = part_path(&path);
+        fs::write(&part, serde_json::to_vec(res)?)?;
+        fs::rename(part, path)?;
+
+        Ok(())
+    }
+
+    pub(crate) fn is_fresh(&self, res: &CachedResponse) -> bool {
+        unix_time().saturating_sub(res.fetched_at) < self.ttl.as_secs()
+    }
+}
+
+pub(crate) fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Copies `src` to `dest` through a temporary file, so `dest` is never left half written.
+pub(crate) async fn copy_file(src: &Path, dest: &Path) -> Result<()> {
+    let part = copy_to_part(src, dest).await?;
+    tokio::fs::rename(&part, dest).await?;
+
+    Ok(())
+}
+
+async fn copy_to_part(src: &Path, dest: &Path) -> Result<PathBuf> {
+    let part = part_path(dest);
+    if let Err(e) = tokio::fs::copy(src, &part).await {
+        let _ = tokio::fs::remove_file(&part).await;
+        return Err(e.into());
+    }
+    Ok(part)
+}
+
+/// Temporary file next to `dest` that no other process uses, e.g. `server.jar.1234-9f3a….part`.
+///
+/// The cache is shared by concurrent installs, which must never write into the same file.
+pub(crate) fn part_path(dest: &Path) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    // randomly seeded for every process
+    let random = RandomState::new().hash_one(nanos);
+
+    let mut part = dest.as_os_str().to_owned();
+    part.push(format!(".{}-{:016x}.part", process::id(), random));
+    PathBuf::from(part)
+}
+
+/// Moves the complete `part` to the cache entry `dest`. If a concurrent install added the entry
+/// in the meantime it is kept and `part` is discarded, so an entry is never replaced while in use.
+async fn persist(part: &Path, dest: &Path) -> Result<()> {
+    if tokio::fs::try_exists(dest).await? {
+        tokio::fs::remove_file(part).await?;
+        return Ok(());
+    }
+    tokio::fs::rename(part, dest).await?;
+
+    Ok(())
+}
+
+fn checksum_dir(checksum: &Checksum) -> String {
+    let algorithm = match checksum {
+        Checksum::Sha256(_) => "sha256",
+        Checksum::Sha1(_) => "sha1",
+        Checksum::Md5(_) => "md5",
+    };
+    format!("{}-{}", algorithm, checksum.value().to_lowercase())
+}
+
+fn is_partial(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "part")
+}
+
+fn remove_empty_dirs(dir: &Path) -> Result<()> {
+    for entry in WalkDir::new(dir).contents_first(true).min_depth(1) {
+        let entry = entry.map_err(io::Error::from)?;
+        if entry.file_type().is_dir() && fs::read_dir(entry.path())?.next().is_none() {
+            fs::remove_dir(entry.path())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use crate::args::AdoptArgs;
+use crate::commands::register;
+use automc::config::ApiConfig;
+use automc::error::*;
+use automc::http::Http;
+use automc::inspect::{find_build, inspect_jar};
+use automc::lock::{Lockfile, LOCKFILE};
+use spinners::{Spinner, Spinners};
+use std::path::PathBuf;
+
+/// Number of the newest builds compared against the jar's checksum.
+const BUILD_SEARCH_LIMIT: usize = 100;
+
+pub async fn execute(args: AdoptArgs, api: &ApiConfig, http: &Http) -> Result<()> {
+    let dir = &args.dir;
+    let jar = dir.join("server.jar");
+    if !jar.exists() {
+        return Err(Error::InvalidArgument(format!(
+            "{} not found",
+            jar.display()
+        )));
+    }
+
+    let info = inspect_jar(&jar)?;
+    let (distribution, version) = match (info.distribution, info.version) {
+        (Some(distribution), Some(version)) => (distribution, version),
+        _ => {
+            return Err(Error::Other(format!(
+                "can't identify the distribution and version of {}",
+                jar.display()
+            )))
+        }
+    };
+    println!("✔ Identified {} {}", distribution, version);
+
+    let java_path = args.java_path.map(PathBuf::from);
+    if Lockfile::load(dir)?.is_some() {
+        let name = register(dir, args.name.as_deref(), distribution, java_path)?;
+        println!("✔ Registered as {}", name);
+        println!("✔ Keeping existing {}", LOCKFILE);
+        return Ok(());
+    }
+
+    let provider = distribution.provider(http, api);
+    let mut sp = Spinner::new(Spinners::Dots, "Identifying build".into());
+    let artifact = match &info.build {
+        Some(build) => {
+            // only recorded if the jar matches the build's checksum. Fabric publishes none,
+            // and its jars don't name the installer that built them
+            let artifact = provider.resolve(&version, build).await?;
+            match &artifact.checksum {
+                Some(checksum) if checksum.matches_file(&jar)? => Some(artifact),
+                _ => None,
+            }
+        }
+        None => find_build(provider.as_ref(), &version, &jar, BUILD_SEARCH_LIMIT).await?,
+    };
+    match &artifact {
+        Some(artifact) => sp.stop_and_persist("✔", format!("Identified build {}", artifact.build)),
+        None => sp.stop_and_persist(
+            "✘",
+            format!(
+                "Unknown build, no {} written. `automc update` installs a known build",
+                LOCKFILE
+            ),
+        ),
+    }
+
+    let name = register(dir, args.name.as_deref(), distribution, java_path.clone())?;
+    println!("✔ Registered as {}", name);
+
+    if let Some(artifact) = artifact {
+        let java_path = java_path.unwrap_or_else(|| "java".into());
+        Lockfile::new(&artifact, &java_path).save(dir)?;
+        println!("✔ Wrote {}", LOCKFILE);
+    }
+
+    Ok(())
+}
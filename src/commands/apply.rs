@@ -0,0 +1,28 @@
+use crate::args::ApplyArgs;
+use crate::commands::register;
+use automc::cache::Cache;
+use automc::config::ApiConfig;
+use automc::error::*;
+use automc::http::Http;
+use automc::spec::{apply, Spec, SPEC_FILE};
+
+pub async fn execute(args: ApplyArgs, api: &ApiConfig, http: &Http) -> Result<()> {
+    let file = args.file.unwrap_or_else(|| args.dir.join(SPEC_FILE));
+    let spec = Spec::load(&file)?;
+
+    let cache = match args.no_cache {
+        true => None,
+        false => Some(Cache::new(Cache::default_dir()?)),
+    };
+    let changes = apply(&spec, &args.dir, http, api, cache).await?;
+    register(&args.dir, None, spec.distribution, Some(spec.java.clone()))?;
+
+    if changes.is_empty() {
+        println!("✔ {} is up to date", args.dir.display());
+    }
+    for change in changes {
+        println!("✔ {}", change);
+    }
+
+    Ok(())
+}
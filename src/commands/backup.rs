@@ -0,0 +1,72 @@
+use crate::args::BackupArgs;
+use crate::commands::server_dir;
+use automc::error::*;
+use spinners::{Spinner, Spinners};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+pub async fn execute(args: BackupArgs) -> Result<()> {
+    let dir = server_dir(&args.dir)?.canonicalize()?;
+
+    let output = match args.output {
+        Some(output) => output,
+        None => {
+            let name = dir
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| "server".to_string());
+            let time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            format!("{}-{}.zip", name, time).into()
+        }
+    };
+
+    let mut sp = Spinner::new(Spinners::Dots, format!("Backing up {}", dir.display()));
+    tokio::task::spawn_blocking(move || write_archive(&dir, &output))
+        .await
+        .map_err(|e| Error::Other(e.to_string()))??;
+    sp.stop_and_persist("✔", "Finished backup".to_string());
+
+    Ok(())
+}
+
+fn write_archive(dir: &Path, output: &Path) -> Result<()> {
+    let output_abs = std::path::absolute(output)?;
+    let mut zip = ZipWriter::new(File::create(output)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for entry in WalkDir::new(dir) {
+        let entry = entry.map_err(io::Error::from)?;
+        let path = entry.path();
+        // don't archive the archive when it is written into the server directory
+        if path == output_abs {
+            continue;
+        }
+
+        let name = path
+            .strip_prefix(dir)
+            .map_err(|e| Error::Other(e.to_string()))?
+            .to_string_lossy()
+            .replace('\\', "/");
+        if name.is_empty() {
+            continue;
+        }
+
+        if entry.file_type().is_dir() {
+            zip.add_directory(name, options).map_err(io::Error::from)?;
+        } else {
+            zip.start_file(name, options).map_err(io::Error::from)?;
+            io::copy(&mut File::open(path)?, &mut zip)?;
+        }
+    }
+
+    zip.finish().map_err(io::Error::from)?;
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use crate::args::BuildsArgs;
+use crate::commands::print_table;
+use automc::config::ApiConfig;
+use automc::distribution::Channel;
+use automc::error::*;
+use automc::http::Http;
+
+pub async fn execute(args: BuildsArgs, api: &ApiConfig, http: &Http) -> Result<()> {
+    let provider = args.distribution.provider(http, api);
+    let version = automc::resolve_version(provider.as_ref(), &args.version).await?;
+    let channel = args
+        .channel
+        .as_deref()
+        .map(str::parse::<Channel>)
+        .transpose()?;
+
+    let mut builds = provider.builds(&version).await?;
+    if let Some(channel) = channel {
+        builds.retain(|b| b.channel == channel);
+    }
+    builds.truncate(args.limit);
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&builds)?);
+        return Ok(());
+    }
+
+    println!("{} {}", args.distribution, version);
+    let mut rows = vec![["BUILD", "CHANNEL", "TIME", "CHANGES"].map(String::from)];
+    for build in &builds {
+        let changes = match build.changes.as_slice() {
+            [] => "-".to_string(),
+            [change] => change.summary.clone(),
+            [change, rest @ ..] => format!("{} (+{} more)", change.summary, rest.len()),
+        };
+        rows.push([
+            build.id.clone(),
+            build.channel.to_string(),
+            build.time.clone().unwrap_or_else(|| "-".to_string()),
+            changes,
+        ]);
+    }
+    print_table(&rows);
+
+    Ok(())
+}
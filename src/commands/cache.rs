@@ -0,0 +1,79 @@
+use crate::args::{CacheArgs, CacheCommand};
+use automc::cache::Cache;
+use automc::error::*;
+use std::time::{Duration, SystemTime};
+
+pub async fn execute(args: CacheArgs) -> Result<()> {
+    let cache = Cache::new(Cache::default_dir()?);
+
+    match args.command {
+        CacheCommand::List => {
+            let entries = cache.list()?;
+            if let Some(size) = cache.build_tools_size()? {
+                println!(
+                    "BuildTools workspace: {} in {}",
+                    format_size(size),
+                    cache.build_tools_dir().display()
+                );
+            }
+            if entries.is_empty() {
+                println!("Cache is empty ({})", cache.dir().display());
+                return Ok(());
+            }
+
+            let now = SystemTime::now();
+            for entry in &entries {
+                let days = now
+                    .duration_since(entry.last_used)
+                    .unwrap_or_default()
+                    .as_secs()
+                    / 86400;
+                println!(
+                    "{:>10}  {:>4}d ago  {}",
+                    format_size(entry.size),
+                    days,
+                    entry.key
+                );
+            }
+
+            let total = entries.iter().map(|e| e.size).sum();
+            println!(
+                "{} files, {} in {}",
+                entries.len(),
+                format_size(total),
+                cache.dir().display()
+            );
+        }
+        CacheCommand::Prune { older_than } => {
+            let removed = cache.prune(Duration::from_secs(older_than * 86400))?;
+            for entry in &removed {
+                println!("Removed {}", entry.key);
+            }
+
+            let total = removed.iter().map(|e| e.size).sum();
+            println!("✔ Freed {}", format_size(total));
+        }
+        CacheCommand::Clear => {
+            let total = cache.clear()?;
+            println!("✔ Freed {}", format_size(total));
+        }
+    }
+
+    Ok(())
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{} B", bytes),
+        _ => format!("{:.1} {}", size, UNITS[unit]),
+    }
+}
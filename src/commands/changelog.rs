@@ -0,0 +1,82 @@
+use crate::args::ChangelogArgs;
+use crate::commands::server_dir;
+use crate::commands::update::target;
+use automc::builds_between;
+use automc::config::ApiConfig;
+use automc::distribution::Build;
+use automc::error::*;
+use automc::http::Http;
+use automc::inspect::inspect_dir;
+
+pub async fn execute(args: ChangelogArgs, api: &ApiConfig, http: &Http) -> Result<()> {
+    let dir = &server_dir(&args.dir)?;
+    let installed = inspect_dir(dir)?;
+    let provider = installed.distribution.provider(http, api);
+
+    let (target_version, target_build) = target(
+        provider.as_ref(),
+        &installed.version,
+        installed.build.as_deref(),
+        args.version.as_deref(),
+        args.build.as_deref(),
+        args.channel.as_deref(),
+    )
+    .await?;
+
+    let target_builds = provider.builds(&target_version).await?;
+    let builds: Vec<Build> = if target_version == installed.version {
+        builds_between(&target_builds, installed.build.as_deref(), &target_build.id)
+            .into_iter()
+            .cloned()
+            .collect()
+    } else {
+        // every build of the new version, then what the installed version got since
+        let installed_builds = provider.builds(&installed.version).await?;
+        let newer = installed_builds
+            .iter()
+            .take_while(|b| Some(&b.id) != installed.build.as_ref());
+        builds_between(&target_builds, None, &target_build.id)
+            .into_iter()
+            .chain(newer)
+            .cloned()
+            .collect()
+    };
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&builds)?);
+        return Ok(());
+    }
+
+    println!(
+        "{} {} build {} → {} build {}",
+        installed.distribution,
+        installed.version,
+        installed.build.as_deref().unwrap_or("unknown"),
+        target_version,
+        target_build.id
+    );
+    if installed.build.is_none() {
+        println!("The installed build is unknown, showing every build up to the target");
+    }
+    if builds.is_empty() {
+        println!("✔ Already up to date");
+        return Ok(());
+    }
+
+    for build in &builds {
+        println!();
+        match &build.time {
+            Some(time) => println!("Build {} ({})", build.id, time),
+            None => println!("Build {}", build.id),
+        }
+        if build.changes.is_empty() {
+            println!("  no changes listed");
+        }
+        for change in &build.changes {
+            let commit = change.commit.get(..7).unwrap_or(&change.commit);
+            println!("  {} {}", commit, change.summary);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use automc::config::{ApiConfig, Config};
+use automc::error::*;
+use automc::http::Http;
+use automc::java::java_versions;
+use std::process::Command;
+
+pub async fn execute(config: &Config, api: &ApiConfig, http: &Http) -> Result<()> {
+    let mut healthy = true;
+
+    report(
+        true,
+        format!(
+            "Config {} (EULA accepted: {})",
+            Config::path()?.display(),
+            config.accepted_eula
+        ),
+    );
+
+    match java_versions() {
+        Ok(paths) => {
+            for path in paths {
+                match Command::new(&path).arg("-version").output() {
+                    // `java -version` prints to stderr
+                    Ok(output) if output.status.success() => {
+                        let stderr = String::from_utf8_lossy(&output.stderr);
+                        let version = stderr.lines().next().unwrap_or_default();
+                        report(true, format!("Java {}: {}", path.display(), version));
+                    }
+                    _ => {
+                        healthy = false;
+                        report(false, format!("Java {} does not run", path.display()));
+                    }
+                }
+            }
+        }
+        Err(e) => {
+            healthy = false;
+            report(false, format!("No Java installation found: {}", e));
+        }
+    }
+
+    let apis = [
+        (
+            "PaperMC Fill",
+            format!("{}/v3/projects", api.fill.trim_end_matches('/')),
+        ),
+        (
+            "PaperMC",
+            format!("{}/v2/projects", api.papermc.trim_end_matches('/')),
+        ),
+        (
+            "Purpur",
+            format!("{}/v2/purpur/", api.purpur.trim_end_matches('/')),
+        ),
+        (
+            "Fabric",
+            format!("{}/v2/versions/", api.fabric.trim_end_matches('/')),
+        ),
+        (
+            "Mojang",
+            format!(
+                "{}/mc/game/version_manifest.json",
+                api.mojang.trim_end_matches('/')
+            ),
+        ),
+        (
+            "Spigot",
+            format!(
+                "{}/jenkins/job/BuildTools/api/json",
+                api.spigot.trim_end_matches('/')
+            ),
+        ),
+    ];
+    for (name, url) in apis {
+        // bypass the metadata cache, this checks the connection itself
+        let res = http.client().get(&url).send().await;
+        match res.and_then(|res| res.error_for_status()) {
+            Ok(_) => report(true, format!("{} API reachable", name)),
+            Err(e) => {
+                healthy = false;
+                report(false, format!("{} API unreachable: {}", name, e));
+            }
+        }
+    }
+
+    if !healthy {
+        return Err(Error::Other("some checks failed".to_string()));
+    }
+
+    Ok(())
+}
+
+fn report(ok: bool, message: String) {
+    println!("{} {}", if ok { "✔" } else { "✘" }, message);
+}
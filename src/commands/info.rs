@@ -0,0 +1,68 @@
+use crate::args::InfoArgs;
+use crate::commands::server_dir;
+use automc::distribution::eula_accepted;
+use automc::error::*;
+use automc::lock::{Lockfile, LOCKFILE};
+use automc::registry::{server_port, Registry};
+use std::fs;
+
+pub async fn execute(args: InfoArgs) -> Result<()> {
+    let dir = &server_dir(&args.dir)?;
+    if !dir.is_dir() {
+        return Err(Error::InvalidArgument(format!(
+            "{} is not a directory",
+            dir.display()
+        )));
+    }
+
+    let registry = Registry::load()?;
+    match registry
+        .find_by_path(dir)
+        .and_then(|name| Some((name, registry.get(name)?)))
+    {
+        Some((name, instance)) => {
+            println!("Name:         {}", name);
+            if let Some(java) = &instance.java_path {
+                println!("Java:         {}", java.display());
+            }
+        }
+        None => println!("Name:         not registered"),
+    }
+    println!("Directory:    {}", dir.canonicalize()?.display());
+
+    let jar = dir.join("server.jar");
+    match fs::metadata(&jar) {
+        Ok(meta) => println!("Server jar:   {} ({} bytes)", jar.display(), meta.len()),
+        Err(_) => println!("Server jar:   missing"),
+    }
+
+    match Lockfile::load(dir)? {
+        Some(lock) => println!(
+            "Installed:    {} {} build {}",
+            lock.distribution, lock.version, lock.build
+        ),
+        None => println!("Installed:    unknown (no {})", LOCKFILE),
+    }
+
+    match server_port(dir) {
+        Some(port) => println!("Port:         {}", port),
+        None => println!("Port:         unknown"),
+    }
+
+    let script = ["start.sh", "start.bat"]
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists());
+    match script {
+        Some(script) => println!("Start script: {}", script.display()),
+        None => println!("Start script: missing"),
+    }
+
+    let eula = eula_accepted(dir);
+    println!(
+        "EULA:         {}",
+        if eula { "accepted" } else { "not accepted" }
+    );
+
+    Ok(())
+}
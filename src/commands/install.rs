@@ -0,0 +1,331 @@
+use crate::args::InstallArgs;
+use crate::commands::register;
+use crate::prompt::{is_interactive, only_stable, value_or_prompt};
+use automc::cache::Cache;
+use automc::config::{ApiConfig, Config};
+use automc::distribution::{
+    Artifact, Build, BuildToolsOptions, Channel, Compile, Distribution, InstallOptions, Provider,
+    Version,
+};
+use automc::error::*;
+use automc::http::Http;
+use automc::java::java_versions;
+use automc::latest_build;
+use automc::lock::{Lockfile, LOCKFILE};
+use automc::registry::Registry;
+use inquire::{Confirm, Select, Text};
+use itertools::Itertools;
+use spinners::{Spinner, Spinners};
+use std::path::PathBuf;
+
+pub async fn execute(
+    args: InstallArgs,
+    config: &mut Config,
+    api: &ApiConfig,
+    http: &Http,
+) -> Result<()> {
+    let dir: PathBuf = value_or_prompt(args.dir.clone(), "--dir", || {
+        Ok(Text::new("Select directory")
+            .with_help_message("leave empty for current directory")
+            .prompt()?
+            .trim()
+            .into())
+    })?;
+    // an empty path can't be resolved, it means the current directory
+    let dir = match dir.as_os_str().is_empty() {
+        true => PathBuf::from("."),
+        false => dir,
+    };
+    if let Some(name) = &args.name {
+        Registry::load()?.check_name(name, &dir)?;
+    }
+
+    let lock = match args.locked {
+        true => Some(Lockfile::load(&dir)?.ok_or_else(|| {
+            Error::InvalidArgument(format!("no {} in {}", LOCKFILE, dir.display()))
+        })?),
+        false => None,
+    };
+
+    let distribution = match &lock {
+        Some(lock) => lock.distribution,
+        None => value_or_prompt(args.distribution, "--distribution", || {
+            Ok(Select::new("Select distribution", automc::distributions()).prompt()?)
+        })?,
+    };
+
+    if !config.accepted_eula {
+        let accepted = args.accept_eula
+            || value_or_prompt(None, "--accept-eula", || {
+                Ok(Confirm::new("Do you accept the EULA?")
+                    .with_help_message("https://www.minecraft.net/eula")
+                    .with_default(true)
+                    .prompt()?)
+            })?;
+
+        if !accepted {
+            return Ok(());
+        } else {
+            config.accepted_eula = true;
+            config.save()?;
+        }
+    }
+
+    let locked_java = lock
+        .as_ref()
+        .map(|lock| lock.java_path.display().to_string());
+    let java_path = value_or_prompt(args.java_path.clone().or(locked_java), "--java", || {
+        let mut java_versions = java_versions()?
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<String>>();
+        java_versions.push("Custom".to_string());
+        java_versions.push("use JAVA_HOME".to_string());
+
+        let selected = Select::new("Select java version", java_versions).prompt()?;
+
+        let path = match selected.as_str() {
+            "Custom" => Text::new("Path to custom java binary")
+                .with_help_message("eg. /usr/lib/jvm/bin/java")
+                .prompt()?,
+            "use JAVA_HOME" => "java".to_string(),
+            _ => selected,
+        };
+
+        Ok(path)
+    })?;
+    let java_path = PathBuf::from(java_path);
+
+    let provider = distribution.provider(http, api);
+
+    let artifact = match &lock {
+        Some(lock) => {
+            let artifact = lock.artifact();
+            println!(
+                "✔ Locked {} {} build {} ({})",
+                artifact.distribution, artifact.version, artifact.build, artifact.file_name
+            );
+            artifact
+        }
+        None => resolve(provider.as_ref(), &args).await?,
+    };
+    if let Some(java) = artifact.java {
+        println!("Requires {}", java);
+    }
+    let build_tools = build_tools_options(distribution, &args, lock.as_ref())?;
+
+    let options = InstallOptions {
+        java_path,
+        skip_verify: args.skip_verify,
+        cache: match args.no_cache {
+            true => None,
+            false => Some(Cache::new(Cache::default_dir()?)),
+        },
+        memory: args.memory.clone(),
+        keep_build_cache: args.keep_build_cache,
+        build_tools,
+    };
+    automc::install(provider.as_ref(), &artifact, &dir, &options).await?;
+
+    let name = register(
+        &dir,
+        args.name.as_deref(),
+        distribution,
+        Some(options.java_path.clone()),
+    )?;
+    println!("✔ Registered as {}", name);
+
+    if is_interactive() {
+        Text::new("Press <ENTER> to exit...").prompt()?;
+    }
+    Ok(())
+}
+
+async fn resolve(provider: &dyn Provider, args: &InstallArgs) -> Result<Artifact> {
+    let mut sp = Spinner::new(Spinners::Dots, "Downloading metadata".into());
+    let versions = provider.versions().await?;
+    sp.stop_and_persist("✔", "Finished downloading metadata".into());
+
+    let version = select_version(versions, args)?;
+
+    let mut sp = Spinner::new(Spinners::Dots, "Downloading build metadata".into());
+    let builds = provider.builds(&version).await?;
+    let installers = provider.installers().await?;
+    sp.stop_and_persist("✔", "Finished downloading build metadata".into());
+
+    let build = select_build(builds, args)?;
+    let installer = select_installer(installers, args)?;
+
+    let mut sp = Spinner::new(Spinners::Dots, "Resolving download".into());
+    let artifact = match installer {
+        Some(installer) => {
+            provider
+                .resolve_with_installer(&version, &build, &installer)
+                .await?
+        }
+        None => provider.resolve(&version, &build).await?,
+    };
+    sp.stop_and_persist(
+        "✔",
+        format!(
+            "Resolved {} {} build {} ({})",
+            artifact.distribution, artifact.version, artifact.build, artifact.file_name
+        ),
+    );
+
+    Ok(artifact)
+}
+
+fn select_version(versions: Vec<Version>, args: &InstallArgs) -> Result<String> {
+    if let Some(version) = &args.version {
+        return automc::find_version(&versions, version);
+    }
+
+    value_or_prompt(None, "--version", || {
+        let mut options = versions;
+        if options.iter().any(|v| !v.stable) && only_stable(args.unstable, "Only stable versions?")?
+        {
+            options.retain(|v| v.stable);
+        }
+
+        Ok(Select::new("Select version", options).prompt()?.id)
+    })
+}
+
+fn select_build(builds: Vec<Build>, args: &InstallArgs) -> Result<String> {
+    let channel = args
+        .channel
+        .as_deref()
+        .map(str::parse::<Channel>)
+        .transpose()?;
+
+    let channel = match args.build.as_deref() {
+        Some("latest") => channel.unwrap_or(Channel::Default),
+        Some(build) => {
+            return builds
+                .into_iter()
+                .find(|b| b.id == build)
+                .map(|b| b.id)
+                .ok_or_else(|| Error::InvalidArgument(format!("unknown build {}", build)));
+        }
+        None if is_interactive() => {
+            let mut options = builds;
+            if let Some(channel) = channel {
+                options.retain(|b| b.channel == channel);
+            }
+            if options.is_empty() {
+                return Err(Error::InvalidArgument("no builds available".to_string()));
+            }
+            // start at the newest stable build
+            let cursor = options
+                .iter()
+                .position(|b| b.channel == Channel::Default)
+                .unwrap_or(0);
+
+            return Ok(Select::new("Select build", options)
+                .with_starting_cursor(cursor)
+                .prompt()?
+                .id);
+        }
+        None => {
+            let options = builds
+                .iter()
+                .map(|b| b.channel)
+                .unique()
+                .collect::<Vec<Channel>>();
+
+            match channel {
+                Some(channel) => channel,
+                None if options.len() == 1 => options[0],
+                None => return Err(Error::MissingArgument("--build")),
+            }
+        }
+    };
+
+    latest_build(&builds, channel)
+        .map(|b| b.id.clone())
+        .ok_or_else(|| Error::InvalidArgument(format!("no {} builds available", channel)))
+}
+
+/// Installer version from `--installer` or asked for, `None` for the provider's default.
+fn select_installer(installers: Vec<Version>, args: &InstallArgs) -> Result<Option<String>> {
+    if installers.is_empty() {
+        return match &args.installer {
+            Some(_) => Err(Error::InvalidArgument(
+                "--installer is only supported for Fabric".to_string(),
+            )),
+            None => Ok(None),
+        };
+    }
+
+    match args.installer.as_deref() {
+        Some("latest") => installers
+            .into_iter()
+            .find(|i| i.stable || args.unstable)
+            .map(|i| Some(i.id))
+            .ok_or_else(|| Error::InvalidArgument("no installer available".to_string())),
+        Some(installer) => installers
+            .into_iter()
+            .find(|i| i.id == installer)
+            .map(|i| Some(i.id))
+            .ok_or_else(|| Error::InvalidArgument(format!("unknown installer {}", installer))),
+        None if is_interactive() => {
+            let mut options = installers;
+            if options.iter().any(|i| !i.stable)
+                && only_stable(args.unstable, "Only stable installers?")?
+            {
+                options.retain(|i| i.stable);
+            }
+            Ok(Some(Select::new("Select installer", options).prompt()?.id))
+        }
+        None => Ok(None),
+    }
+}
+
+/// BuildTools options from the command line or the lockfile, or asked for when building Spigot
+/// or CraftBukkit interactively without any passed.
+fn build_tools_options(
+    distribution: Distribution,
+    args: &InstallArgs,
+    lock: Option<&Lockfile>,
+) -> Result<BuildToolsOptions> {
+    let mut options = args
+        .build_tools
+        .options_or(lock.and_then(|lock| lock.build_tools.as_ref()));
+    let built = matches!(
+        distribution,
+        Distribution::Spigot | Distribution::CraftBukkit
+    );
+    if !built || args.build_tools.is_set() || lock.is_some() || !is_interactive() {
+        return Ok(options);
+    }
+
+    let customize = Confirm::new("Customize BuildTools options?")
+        .with_help_message("e.g. build the remapped jars")
+        .with_default(false)
+        .prompt()?;
+    if !customize {
+        return Ok(options);
+    }
+
+    // CraftBukkit always compiles CraftBukkit
+    if distribution == Distribution::Spigot {
+        options.compile = Some(
+            Select::new(
+                "Select server to compile",
+                vec![Compile::Spigot, Compile::CraftBukkit],
+            )
+            .prompt()?,
+        );
+    }
+    options.remapped = Confirm::new("Build the remapped jars?")
+        .with_help_message("installs Mojang mapped jars into the local Maven repository")
+        .with_default(false)
+        .prompt()?;
+    let final_name = Text::new("Name of the built jar")
+        .with_help_message("leave empty for the default name")
+        .prompt()?;
+    options.final_name = Some(final_name).filter(|name| !name.is_empty());
+
+    Ok(options)
+}
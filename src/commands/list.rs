@@ -0,0 +1,38 @@
+use crate::commands::print_table;
+use automc::error::*;
+use automc::inspect::inspect_dir;
+use automc::registry::{server_port, Registry};
+
+pub async fn execute() -> Result<()> {
+    let registry = Registry::load()?;
+    if registry.instances.is_empty() {
+        println!("No servers registered, install one with `automc install`");
+        return Ok(());
+    }
+
+    let mut rows =
+        vec![["NAME", "DISTRIBUTION", "VERSION", "BUILD", "PORT", "PATH"].map(String::from)];
+    for (name, instance) in &registry.instances {
+        let installed = inspect_dir(&instance.path).ok();
+        rows.push([
+            name.clone(),
+            instance.distribution.to_string(),
+            installed
+                .as_ref()
+                .map(|i| i.version.clone())
+                .unwrap_or_else(|| "-".to_string()),
+            installed
+                .and_then(|i| i.build)
+                .unwrap_or_else(|| "-".to_string()),
+            // the port may have changed since the server was registered
+            server_port(&instance.path)
+                .or(instance.port)
+                .map(|p| p.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            instance.path.display().to_string(),
+        ]);
+    }
+    print_table(&rows);
+
+    Ok(())
+}
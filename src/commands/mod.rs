@@ -0,0 +1,64 @@
+use automc::distribution::Distribution;
+use automc::error::*;
+use automc::registry::{Instance, Registry};
+use std::path::{Path, PathBuf};
+
+pub mod adopt;
+pub mod apply;
+pub mod backup;
+pub mod builds;
+pub mod cache;
+pub mod changelog;
+pub mod doctor;
+pub mod info;
+pub mod install;
+pub mod list;
+pub mod outdated;
+pub mod remove;
+pub mod run;
+pub mod update;
+
+/// Directory of the registered server named `target`, otherwise `target` itself.
+pub fn server_dir(target: &Path) -> Result<PathBuf> {
+    let registry = Registry::load()?;
+    let instance = target.to_str().and_then(|name| registry.get(name));
+
+    Ok(match instance {
+        Some(instance) => instance.path.clone(),
+        None => target.to_path_buf(),
+    })
+}
+
+/// Adds the server in `dir` to the registry, or updates its entry. Returns its name.
+pub fn register(
+    dir: &Path,
+    name: Option<&str>,
+    distribution: Distribution,
+    java_path: Option<PathBuf>,
+) -> Result<String> {
+    let mut registry = Registry::load()?;
+    let name = registry.register(name, Instance::new(dir, distribution, java_path)?)?;
+    registry.save()?;
+
+    Ok(name)
+}
+
+/// Prints `rows` as left aligned columns, the first row being the header.
+pub fn print_table<const N: usize>(rows: &[[String; N]]) {
+    let mut widths = [0; N];
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    for row in rows {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    }
+}
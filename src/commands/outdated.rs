@@ -0,0 +1,170 @@
+use crate::args::OutdatedArgs;
+use crate::commands::{print_table, server_dir};
+use automc::config::ApiConfig;
+use automc::distribution::{Channel, Distribution};
+use automc::error::*;
+use automc::http::Http;
+use automc::inspect::inspect_dir;
+use automc::latest_build;
+use automc::registry::Registry;
+use automc::version::MinecraftVersion;
+use futures_util::future::join_all;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Debug)]
+struct Status {
+    name: Option<String>,
+    dir: PathBuf,
+    distribution: Option<Distribution>,
+    version: Option<String>,
+    build: Option<String>,
+    /// Newest build of the installed version on the installed build's channel.
+    latest_build: Option<String>,
+    /// Newest stable version of the distribution.
+    latest_version: Option<String>,
+    outdated: bool,
+    error: Option<String>,
+}
+
+pub async fn execute(args: OutdatedArgs, api: &ApiConfig, http: &Http) -> Result<()> {
+    let registry = Registry::load()?;
+    let servers: Vec<(Option<String>, PathBuf)> = match args.dirs.is_empty() {
+        true => registry
+            .instances
+            .iter()
+            .map(|(name, instance)| (Some(name.clone()), instance.path.clone()))
+            .collect(),
+        false => args
+            .dirs
+            .iter()
+            .map(|target| {
+                let dir = server_dir(target)?;
+                let name = registry.find_by_path(&dir).map(str::to_string);
+                Ok((name, dir))
+            })
+            .collect::<Result<_>>()?,
+    };
+
+    let statuses = join_all(servers.into_iter().map(|(name, dir)| async move {
+        match status(name.clone(), &dir, api, http).await {
+            Ok(status) => status,
+            Err(e) => Status {
+                name,
+                dir,
+                distribution: None,
+                version: None,
+                build: None,
+                latest_build: None,
+                latest_version: None,
+                outdated: false,
+                error: Some(e.to_string()),
+            },
+        }
+    }))
+    .await;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&statuses)?);
+        return Ok(());
+    }
+    if statuses.is_empty() {
+        println!("No servers registered, install one with `automc install`");
+        return Ok(());
+    }
+
+    let mut rows = vec![[
+        "SERVER",
+        "DISTRIBUTION",
+        "VERSION",
+        "BUILD",
+        "LATEST BUILD",
+        "LATEST VERSION",
+        "OUTDATED",
+    ]
+    .map(String::from)];
+    for status in &statuses {
+        let field = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+        let row = [
+            status
+                .name
+                .clone()
+                .unwrap_or_else(|| status.dir.display().to_string()),
+            status
+                .distribution
+                .map(|d| d.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            field(&status.version),
+            field(&status.build),
+            field(&status.latest_build),
+            field(&status.latest_version),
+            match (&status.error, status.outdated) {
+                (Some(_), _) => "-",
+                (None, true) => "yes",
+                (None, false) => "no",
+            }
+            .to_string(),
+        ];
+        rows.push(row);
+    }
+    print_table(&rows);
+
+    for status in &statuses {
+        if let Some(error) = &status.error {
+            let server = status.name.clone();
+            let server = server.unwrap_or_else(|| status.dir.display().to_string());
+            println!("✘ {}: {}", server, error);
+        }
+    }
+
+    Ok(())
+}
+
+async fn status(name: Option<String>, dir: &Path, api: &ApiConfig, http: &Http) -> Result<Status> {
+    let installed = inspect_dir(dir)?;
+    let provider = installed.distribution.provider(http, api);
+
+    let versions = provider.versions().await?;
+    let latest_version = versions.into_iter().find(|v| v.stable).map(|v| v.id);
+
+    let builds = provider.builds(&installed.version).await?;
+    let channel = builds
+        .iter()
+        .find(|b| Some(&b.id) == installed.build.as_ref())
+        .map(|b| b.channel)
+        .unwrap_or(Channel::Default);
+    let latest_build = latest_build(&builds, channel).map(|b| b.id.clone());
+
+    let outdated = latest_build.is_some() && latest_build != installed.build
+        || latest_version
+            .as_deref()
+            .is_some_and(|latest| newer(latest, &installed.version));
+
+    Ok(Status {
+        name,
+        dir: dir.to_path_buf(),
+        distribution: Some(installed.distribution),
+        version: Some(installed.version),
+        build: installed.build,
+        latest_build,
+        latest_version,
+        outdated,
+        error: None,
+    })
+}
+
+/// Whether `latest` is newer than `installed`. Versions that can't be ordered, e.g. a
+/// snapshot and a release, count as newer when they differ.
+fn newer(latest: &str, installed: &str) -> bool {
+    let parsed = (
+        latest.parse::<MinecraftVersion>(),
+        installed.parse::<MinecraftVersion>(),
+    );
+    match parsed {
+        (Ok(latest), Ok(installed)) => match latest.partial_cmp(&installed) {
+            Some(order) => order.is_gt(),
+            None => true,
+        },
+        _ => latest != installed,
+    }
+}
@@ -0,0 +1,18 @@
+use crate::args::RemoveArgs;
+use automc::error::*;
+use automc::registry::Registry;
+
+pub async fn execute(args: RemoveArgs) -> Result<()> {
+    let mut registry = Registry::load()?;
+    let instance = registry
+        .remove(&args.name)
+        .ok_or_else(|| Error::InvalidArgument(format!("unknown server {}", args.name)))?;
+    registry.save()?;
+
+    println!(
+        "✔ Removed {}, its files in {} are kept",
+        args.name,
+        instance.path.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use crate::args::RunArgs;
+use crate::commands::server_dir;
+use automc::error::*;
+use std::path::Path;
+use std::process::Command;
+
+pub async fn execute(args: RunArgs) -> Result<()> {
+    let dir = server_dir(&args.dir)?.canonicalize()?;
+
+    let status = start_command(&dir)?.current_dir(&dir).status()?;
+    if !status.success() {
+        return Err(Error::Other(format!("server exited with {}", status)));
+    }
+
+    Ok(())
+}
+
+#[cfg(windows)]
+fn start_command(dir: &Path) -> Result<Command> {
+    let script = dir.join("start.bat");
+    if !script.exists() {
+        return Err(Error::Other(format!("{} not found", script.display())));
+    }
+
+    let mut command = Command::new("cmd");
+    command.arg("/C").arg(script);
+    Ok(command)
+}
+
+#[cfg(unix)]
+fn start_command(dir: &Path) -> Result<Command> {
+    let script = dir.join("start.sh");
+    if !script.exists() {
+        return Err(Error::Other(format!("{} not found", script.display())));
+    }
+
+    Ok(Command::new(script))
+}
+
+#[cfg(all(not(unix), not(windows)))]
+fn start_command(dir: &Path) -> Result<Command> {
+    Err(Error::Other("unsupported OS".to_string()))
+}
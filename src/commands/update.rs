@@ -0,0 +1,132 @@
+use crate::args::UpdateArgs;
+use crate::commands::{register, server_dir};
+use automc::cache::Cache;
+use automc::config::ApiConfig;
+use automc::distribution::{Build, Channel, InstallOptions, Provider};
+use automc::error::*;
+use automc::http::Http;
+use automc::inspect::{inspect_dir, Installed};
+use automc::latest_build;
+use automc::registry::Registry;
+use spinners::{Spinner, Spinners};
+use std::path::PathBuf;
+
+pub async fn execute(args: UpdateArgs, api: &ApiConfig, http: &Http) -> Result<()> {
+    let dir = &server_dir(&args.dir)?;
+
+    let Installed {
+        distribution,
+        version,
+        build,
+        java_path,
+        installer,
+        build_tools,
+    } = inspect_dir(dir)?;
+    println!(
+        "Installed: {} {} build {}",
+        distribution,
+        version,
+        build.as_deref().unwrap_or("unknown")
+    );
+
+    let provider = distribution.provider(http, api);
+
+    let mut sp = Spinner::new(Spinners::Dots, "Downloading build metadata".into());
+    let (target_version, target_build) = target(
+        provider.as_ref(),
+        &version,
+        build.as_deref(),
+        args.version.as_deref(),
+        args.build.as_deref(),
+        args.channel.as_deref(),
+    )
+    .await?;
+    sp.stop_and_persist("✔", "Finished downloading build metadata".into());
+
+    if target_version == version && Some(&target_build.id) == build.as_ref() {
+        println!("✔ Already up to date");
+        return Ok(());
+    }
+
+    let mut sp = Spinner::new(Spinners::Dots, "Resolving download".into());
+    // keep the Fabric installer the server was installed with
+    let artifact = match &installer {
+        Some(installer) => {
+            provider
+                .resolve_with_installer(&target_version, &target_build.id, installer)
+                .await?
+        }
+        None => provider.resolve(&target_version, &target_build.id).await?,
+    };
+    sp.stop_and_persist(
+        "✔",
+        format!(
+            "Resolved {} {} build {} ({})",
+            artifact.distribution, artifact.version, artifact.build, artifact.file_name
+        ),
+    );
+
+    let options = InstallOptions {
+        java_path: args
+            .java_path
+            .map(PathBuf::from)
+            .or(java_path)
+            .unwrap_or_else(|| "java".into()),
+        skip_verify: args.skip_verify,
+        cache: match args.no_cache {
+            true => None,
+            false => Some(Cache::new(Cache::default_dir()?)),
+        },
+        memory: None,
+        keep_build_cache: args.keep_build_cache,
+        build_tools: args.build_tools.options_or(build_tools.as_ref()),
+    };
+    automc::update(provider.as_ref(), &artifact, dir, &options).await?;
+
+    // keep the registry entry in sync, servers are registered explicitly by install or adopt
+    if Registry::load()?.find_by_path(dir).is_some() {
+        register(dir, None, distribution, Some(options.java_path.clone()))?;
+    }
+
+    println!(
+        "✔ Updated to {} {} build {}, the previous jar is kept as server.jar.bak",
+        artifact.distribution, artifact.version, artifact.build
+    );
+    Ok(())
+}
+
+/// Version and build to update to: `version` (a constraint, defaults to the installed version)
+/// and `build`, or the newest build on `channel` or else the channel of the installed build.
+pub async fn target(
+    provider: &dyn Provider,
+    installed_version: &str,
+    installed_build: Option<&str>,
+    version: Option<&str>,
+    build: Option<&str>,
+    channel: Option<&str>,
+) -> Result<(String, Build)> {
+    let target_version = match version {
+        Some(target) => automc::resolve_version(provider, target).await?,
+        None => installed_version.to_string(),
+    };
+    let builds = provider.builds(&target_version).await?;
+
+    let target_build = match build {
+        Some(id) if id != "latest" => builds.iter().find(|b| b.id == id),
+        _ => {
+            // stay on the channel of the installed build
+            let installed_channel = builds
+                .iter()
+                .find(|b| Some(b.id.as_str()) == installed_build)
+                .map(|b| b.channel);
+            let channel = match channel {
+                Some(channel) => channel.parse()?,
+                None => installed_channel.unwrap_or(Channel::Default),
+            };
+            latest_build(&builds, channel)
+        }
+    }
+    .ok_or_else(|| Error::InvalidArgument(format!("no matching build for {}", target_version)))?;
+
+    Ok((target_version, target_build.clone()))
+}
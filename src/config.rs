@@ -1,26 +1,164 @@
+//! User configuration stored in `config.toml`.
+
 use crate::error::*;
 use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::PathBuf;
 
-const APP_NAME: &str = "automc";
+pub(crate) const APP_NAME: &str = "automc";
 const CONFIG_NAME: &str = "config";
 
+/// Persistent user configuration. Changes are only written by [`Config::save`].
 #[derive(Default, Serialize, Deserialize, Debug)]
 pub struct Config {
+    /// Whether the user accepted the Minecraft EULA, so it isn't asked for again.
     pub accepted_eula: bool,
+    /// Base URLs of the distribution APIs.
+    #[serde(default)]
+    pub api: ApiConfig,
+    /// HTTP client settings.
+    #[serde(default)]
+    pub http: HttpConfig,
+    /// Retry settings for downloads.
+    #[serde(default)]
+    pub download: DownloadConfig,
+    /// Settings of the local cache.
+    #[serde(default)]
+    pub cache: CacheConfig,
+}
+
+/// Base URLs of the distribution APIs, e.g. to use an internal mirror.
+///
+/// Each URL can also be overridden with an environment variable, see [`ApiConfig::apply_env`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ApiConfig {
+    /// Paper, Folia and Velocity, using the Fill (v3) API.
+    pub fill: String,
+    /// Paper, Folia and Velocity, using the v2 API if Fill can't be reached.
+    pub papermc: String,
+    /// Purpur's API.
+    pub purpur: String,
+    /// Fabric's meta API.
+    pub fabric: String,
+    /// Only the version manifest is fetched from here, everything else uses the URLs it contains.
+    pub mojang: String,
+    /// Spigot's hub, which lists the versions and the BuildTools revision of each one.
+    pub spigot: String,
+}
+
+impl ApiConfig {
+    /// Overrides URLs with the `AUTOMC_FILL_URL`, `AUTOMC_PAPERMC_URL`, `AUTOMC_PURPUR_URL`,
+    /// `AUTOMC_FABRIC_URL`, `AUTOMC_MOJANG_URL` and `AUTOMC_SPIGOT_URL` environment variables.
+    pub fn apply_env(&mut self) {
+        for (var, url) in [
+            ("AUTOMC_FILL_URL", &mut self.fill),
+            ("AUTOMC_PAPERMC_URL", &mut self.papermc),
+            ("AUTOMC_PURPUR_URL", &mut self.purpur),
+            ("AUTOMC_FABRIC_URL", &mut self.fabric),
+            ("AUTOMC_MOJANG_URL", &mut self.mojang),
+            ("AUTOMC_SPIGOT_URL", &mut self.spigot),
+        ] {
+            if let Ok(value) = env::var(var) {
+                *url = value;
+            }
+        }
+    }
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            fill: "https://fill.papermc.io".to_string(),
+            papermc: "https://api.papermc.io".to_string(),
+            purpur: "https://api.purpurmc.org".to_string(),
+            fabric: "https://meta.fabricmc.net".to_string(),
+            mojang: "https://launchermeta.mojang.com".to_string(),
+            spigot: "https://hub.spigotmc.org".to_string(),
+        }
+    }
+}
+
+/// Settings of the HTTP client used for all requests.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// Maximum time to establish a connection.
+    pub connect_timeout_secs: u64,
+    /// Maximum time to wait for data before the connection counts as dropped.
+    pub read_timeout_secs: u64,
+    /// Proxy for HTTP and HTTPS requests, e.g. `http://proxy.example.com:8080`.
+    /// Without it, the `HTTP_PROXY`/`HTTPS_PROXY` environment variables are used.
+    pub proxy: Option<String>,
+    /// Comma separated hosts that bypass `proxy`.
+    pub no_proxy: Option<String>,
+    /// Additional trusted CA certificates (PEM files), e.g. of a TLS-intercepting proxy.
+    pub ca_certificates: Vec<PathBuf>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: 10,
+            read_timeout_secs: 30,
+            proxy: None,
+            no_proxy: None,
+            ca_certificates: Vec::new(),
+        }
+    }
+}
+
+/// Retry settings for downloads.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct DownloadConfig {
+    /// Number of retries after the first attempt failed.
+    pub retries: u32,
+    /// Delay before the first retry, doubled for every further retry.
+    pub backoff_ms: u64,
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        Self {
+            retries: 5,
+            backoff_ms: 500,
+        }
+    }
+}
+
+/// Settings of the local download and metadata cache.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// How long version and build metadata is used without asking the API again.
+    pub metadata_ttl_secs: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            metadata_ttl_secs: 600,
+        }
+    }
 }
 
 impl Config {
+    /// Reads the config file, creating it with the defaults if there is none.
     pub fn load() -> Result<Self> {
         Ok(confy::load(APP_NAME, Some(CONFIG_NAME))?)
     }
 
-    pub fn save(&self) -> Result<()> {
-        Ok(confy::store(APP_NAME, Some(CONFIG_NAME), self)?)
+    /// Location of the config file.
+    pub fn path() -> Result<PathBuf> {
+        Ok(confy::get_configuration_file_path(
+            APP_NAME,
+            Some(CONFIG_NAME),
+        )?)
     }
-}
 
-impl Drop for Config {
-    fn drop(&mut self) {
-        self.save().expect("error saving config")
+    /// Writes the config file. Changes are never saved implicitly.
+    pub fn save(&self) -> Result<()> {
+        Ok(confy::store(APP_NAME, Some(CONFIG_NAME), self)?)
     }
 }
@@ -0,0 +1,473 @@
+use crate::cache::{unix_time, Cache};
+use crate::error::*;
+use clap::ValueEnum;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use spinners::{Spinner, Spinners};
+use std::collections::VecDeque;
+use std::fs::TryLockError;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+use std::time::Duration;
+use std::{env, future};
+use strum::Display;
+use tokio::fs::{self, File};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
+use tokio::process::{Child, Command};
+
+/// Locked while a build uses the persistent workspace.
+const LOCK_FILE: &str = ".lock";
+
+/// Number of BuildTools output lines shown while it runs.
+const VISIBLE_LINES: usize = 8;
+
+/// Options passed to BuildTools when building Spigot.
+///
+/// Recorded in the [`Lockfile`](crate::lock::Lockfile), except for the timeout.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildToolsOptions {
+    /// Server to compile, Spigot if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compile: Option<Compile>,
+    /// Also build the Mojang mapped jars, which BuildTools installs into the local Maven
+    /// repository for plugin development.
+    #[serde(default)]
+    pub remapped: bool,
+    /// File name BuildTools gives the built jar.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_name: Option<String>,
+    /// BuildTools is stopped if it runs longer.
+    #[serde(skip)]
+    pub timeout: Option<Duration>,
+}
+
+/// Server BuildTools compiles.
+#[derive(Debug, Display, Serialize, Deserialize, ValueEnum, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Compile {
+    /// Spigot, the default.
+    #[strum(serialize = "spigot")]
+    Spigot,
+    /// CraftBukkit without Spigot's patches.
+    #[strum(serialize = "craftbukkit")]
+    #[value(name = "craftbukkit")]
+    CraftBukkit,
+}
+
+impl BuildToolsOptions {
+    /// Whether any option changes what BuildTools builds.
+    pub fn is_set(&self) -> bool {
+        self.compile.is_some() || self.remapped || self.final_name.is_some()
+    }
+
+    /// Server BuildTools compiles with these options.
+    pub fn compile(&self) -> Compile {
+        self.compile.unwrap_or(Compile::Spigot)
+    }
+
+    /// File name of the jar BuildTools builds for `version`.
+    pub fn jar_name(&self, version: &str) -> String {
+        match &self.final_name {
+            Some(name) => name.clone(),
+            None => format!("{}-{}.jar", self.compile(), version),
+        }
+    }
+
+    /// Arguments following `--rev <rev>`.
+    fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(compile) = self.compile {
+            args.extend(["--compile".to_string(), compile.to_string()]);
+        }
+        if self.remapped {
+            args.push("--remapped".to_string());
+        }
+        if let Some(name) = &self.final_name {
+            args.extend(["--final-name".to_string(), name.clone()]);
+        }
+        args
+    }
+}
+
+/// Directory BuildTools runs in.
+///
+/// With a cache this is the persistent workspace in the cache directory unless another build
+/// holds its lock, otherwise a new temporary directory. BuildTools only ever runs in directories
+/// automc created, and only temporary ones are removed again.
+pub(crate) struct Workspace {
+    pub dir: PathBuf,
+    temporary: bool,
+    /// Held while the persistent workspace is in use, released on drop.
+    _lock: Option<std::fs::File>,
+}
+
+impl Workspace {
+    pub async fn new(cache: Option<&Cache>) -> Result<Self> {
+        let Some(cache) = cache else {
+            return Self::temporary().await;
+        };
+
+        let dir = cache.build_tools_dir();
+        fs::create_dir_all(&dir).await?;
+
+        // concurrent builds in the same workspace would overwrite each other's files
+        let lock = std::fs::File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join(LOCK_FILE))?;
+        match lock.try_lock() {
+            Ok(()) => Ok(Self {
+                dir,
+                temporary: false,
+                _lock: Some(lock),
+            }),
+            Err(TryLockError::WouldBlock) => {
+                println!("BuildTools workspace is in use by another build, using a temporary one");
+                Self::temporary().await
+            }
+            Err(TryLockError::Error(e)) => Err(e.into()),
+        }
+    }
+
+    async fn temporary() -> Result<Self> {
+        let dir = env::temp_dir().join(format!(
+            "automc-buildtools-{}-{}",
+            process::id(),
+            unix_time()
+        ));
+        // fails if the directory exists, so it can't belong to anyone else
+        fs::create_dir(&dir).await?;
+        Ok(Self {
+            dir,
+            temporary: true,
+            _lock: None,
+        })
+    }
+
+    /// Removes a temporary workspace unless `keep` is set.
+    pub async fn cleanup(&self, keep: bool) -> Result<()> {
+        if !self.temporary {
+            return Ok(());
+        }
+        if keep {
+            println!("BuildTools workspace kept in {}", self.dir.display());
+            return Ok(());
+        }
+
+        let mut sp = Spinner::new(Spinners::Dots, "Deleting temp files...".to_string());
+        fs::remove_dir_all(&self.dir).await?;
+        sp.stop_and_persist("✔", "Finished deleting temp files".to_string());
+        Ok(())
+    }
+
+    /// Runs `BuildTools.jar` in the workspace, showing its latest output and writing all of it
+    /// to `log`. BuildTools is killed when it exceeds the timeout or on Ctrl+C.
+    pub async fn run(
+        &self,
+        java_path: &Path,
+        rev: &str,
+        options: &BuildToolsOptions,
+        log: &Path,
+    ) -> Result<()> {
+        let mut tree = ProcessTree::spawn(
+            Command::new(java_path)
+                .current_dir(&self.dir)
+                .args(["-jar", "BuildTools.jar", "--rev", rev])
+                .args(options.args())
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true),
+        )?;
+
+        let mut stdout = BufReader::new(tree.child.stdout.take().expect("stdout is piped"));
+        let mut stderr = BufReader::new(tree.child.stderr.take().expect("stderr is piped"));
+        let (mut stdout_buf, mut stderr_buf) = (Vec::new(), Vec::new());
+        let mut log_file = BufWriter::new(File::create(log).await?);
+        let mut view = OutputView::new(&format!("Building {} with BuildTools", options.compile()));
+
+        let timeout = async {
+            match options.timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => future::pending().await,
+            }
+        };
+        tokio::pin!(timeout);
+        let _sigint = SigintGuard;
+        let cancel = tokio::signal::ctrl_c();
+        tokio::pin!(cancel);
+
+        let (mut stdout_open, mut stderr_open) = (true, true);
+        let status = loop {
+            // output isn't necessarily UTF-8, e.g. Windows code pages, so it's read as bytes
+            let line = tokio::select! {
+                read = stdout.read_until(b'\n', &mut stdout_buf), if stdout_open => {
+                    if read? == 0 {
+                        stdout_open = false;
+                        continue;
+                    }
+                    take_line(&mut stdout_buf)
+                }
+                read = stderr.read_until(b'\n', &mut stderr_buf), if stderr_open => {
+                    if read? == 0 {
+                        stderr_open = false;
+                        continue;
+                    }
+                    take_line(&mut stderr_buf)
+                }
+                status = tree.child.wait(), if !stdout_open && !stderr_open => break status?,
+                _ = &mut timeout => {
+                    tree.kill().await?;
+                    log_file.flush().await?;
+                    view.fail("BuildTools timed out");
+                    return Err(Error::Other(format!(
+                        "BuildTools didn't finish within {} minutes, see {}",
+                        options.timeout.unwrap_or_default().as_secs() / 60,
+                        log.display()
+                    )));
+                }
+                _ = &mut cancel => {
+                    tree.kill().await?;
+                    log_file.flush().await?;
+                    view.fail("BuildTools was cancelled");
+                    return Err(Error::Other("BuildTools was cancelled".to_string()));
+                }
+            };
+
+            log_file.write_all(line.as_bytes()).await?;
+            log_file.write_all(b"\n").await?;
+            view.push(line);
+        };
+        log_file.flush().await?;
+
+        if !status.success() {
+            view.fail("BuildTools failed");
+            return Err(Error::Other(format!(
+                "BuildTools exited with {}, see {}",
+                status,
+                log.display()
+            )));
+        }
+        view.finish(&format!("Finished building {}", options.compile()));
+
+        Ok(())
+    }
+}
+
+/// BuildTools and the Maven and git processes it starts, so all of them are stopped together.
+///
+/// On unix BuildTools leads its own process group, on Windows it runs in a job object. Killing
+/// only BuildTools would leave its children writing into the workspace after it is unlocked.
+struct ProcessTree {
+    child: Child,
+    #[cfg(windows)]
+    job: job::Job,
+}
+
+impl ProcessTree {
+    fn spawn(command: &mut Command) -> Result<Self> {
+        #[cfg(unix)]
+        command.process_group(0);
+        let child = command.spawn()?;
+
+        Ok(Self {
+            #[cfg(windows)]
+            job: job::Job::assign(&child)?,
+            child,
+        })
+    }
+
+    /// Kills BuildTools and its children and waits for BuildTools to exit.
+    async fn kill(&mut self) -> Result<()> {
+        self.kill_tree()?;
+        self.child.wait().await?;
+        Ok(())
+    }
+
+    fn kill_tree(&self) -> io::Result<()> {
+        #[cfg(unix)]
+        if let Some(pid) = self.child.id() {
+            // a negative pid signals the whole process group, whose id is the pid of BuildTools
+            if unsafe { libc::kill(-(pid as libc::pid_t), libc::SIGKILL) } != 0 {
+                let e = io::Error::last_os_error();
+                // nothing left to kill
+                if e.raw_os_error() != Some(libc::ESRCH) {
+                    return Err(e);
+                }
+            }
+        }
+        #[cfg(windows)]
+        self.job.terminate()?;
+
+        Ok(())
+    }
+}
+
+impl Drop for ProcessTree {
+    fn drop(&mut self) {
+        // BuildTools is still running if the build failed while reading its output
+        if self.child.id().is_some() {
+            let _ = self.kill_tree();
+        }
+    }
+}
+
+#[cfg(windows)]
+mod job {
+    use std::{io, mem, ptr};
+    use tokio::process::Child;
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, TerminateJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    /// Job object whose processes are killed when it is terminated or closed.
+    pub struct Job(HANDLE);
+
+    impl Job {
+        pub fn assign(child: &Child) -> io::Result<Self> {
+            let handle = child
+                .raw_handle()
+                .ok_or_else(|| io::Error::other("BuildTools already exited"))?;
+
+            let job = unsafe { CreateJobObjectW(ptr::null(), ptr::null()) };
+            if job.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let job = Self(job);
+
+            let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { mem::zeroed() };
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            let ok = unsafe {
+                SetInformationJobObject(
+                    job.0,
+                    JobObjectExtendedLimitInformation,
+                    &info as *const _ as *const _,
+                    mem::size_of_val(&info) as u32,
+                )
+            };
+            if ok == 0 || unsafe { AssignProcessToJobObject(job.0, handle as HANDLE) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(job)
+        }
+
+        pub fn terminate(&self) -> io::Result<()> {
+            match unsafe { TerminateJobObject(self.0, 1) } {
+                0 => Err(io::Error::last_os_error()),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            unsafe { CloseHandle(self.0) };
+        }
+    }
+}
+
+/// Restores the default Ctrl+C handling once BuildTools is done.
+///
+/// Tokio never removes the handler it installs for [`tokio::signal::ctrl_c`], which would
+/// leave Ctrl+C without effect for the rest of the run. On Windows the default handler runs
+/// again by itself once nothing listens.
+struct SigintGuard;
+
+impl Drop for SigintGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        unsafe {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+        }
+    }
+}
+
+/// Takes a line read into `buf` without its line ending, replacing invalid UTF-8.
+fn take_line(buf: &mut Vec<u8>) -> String {
+    let line = String::from_utf8_lossy(buf)
+        .trim_end_matches(['\n', '\r'])
+        .to_string();
+    buf.clear();
+    line
+}
+
+/// A spinner followed by the last lines of output, replaced as new lines come in.
+struct OutputView {
+    header: ProgressBar,
+    lines: Vec<ProgressBar>,
+    buffer: VecDeque<String>,
+}
+
+impl OutputView {
+    fn new(message: &str) -> Self {
+        let multi = MultiProgress::new();
+        let header = multi.add(ProgressBar::new_spinner());
+        header.set_style(
+            ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] {msg}").unwrap(),
+        );
+        header.set_message(message.to_string());
+        header.enable_steady_tick(Duration::from_millis(100));
+
+        let lines = (0..VISIBLE_LINES)
+            .map(|_| {
+                let line = multi.add(ProgressBar::new_spinner());
+                line.set_style(ProgressStyle::with_template("  {wide_msg:.dim}").unwrap());
+                line
+            })
+            .collect();
+
+        Self {
+            header,
+            lines,
+            buffer: VecDeque::with_capacity(VISIBLE_LINES),
+        }
+    }
+
+    fn push(&mut self, line: String) {
+        if self.buffer.len() == VISIBLE_LINES {
+            self.buffer.pop_front();
+        }
+        self.buffer.push_back(line);
+
+        for (bar, line) in self.lines.iter().zip(&self.buffer) {
+            bar.set_message(line.clone());
+        }
+    }
+
+    /// Removes the output and keeps the header with `message`.
+    fn finish(&self, message: &str) {
+        for line in &self.lines {
+            line.finish_and_clear();
+        }
+        self.header.finish_with_message(format!("✔ {}", message));
+    }
+
+    /// Keeps the last lines of output on screen to show what went wrong.
+    fn fail(&self, message: &str) {
+        self.header.abandon_with_message(format!("✘ {}", message));
+        for line in &self.lines {
+            line.abandon();
+        }
+    }
+}
+
+/// Cache key of BuildTools.jar, taken from the Jenkins build number in its URL.
+pub(crate) fn build_tools_key(url: &str) -> Option<Vec<String>> {
+    let number = url
+        .strip_suffix("/artifact/target/BuildTools.jar")?
+        .rsplit('/')
+        .next()
+        .filter(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))?;
+
+    Some(vec![
+        "buildtools".to_string(),
+        number.to_string(),
+        "BuildTools.jar".to_string(),
+    ])
+}
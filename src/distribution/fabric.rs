@@ -1,107 +1,126 @@
-use crate::distribution::{download_file, install_server_jar};
+use crate::distribution::{Artifact, Build, Channel, Distribution, Provider, Version};
 use crate::error::*;
-use futures_util::future::join3;
-use inquire::{Confirm, Select};
+use crate::http::Http;
+use async_trait::async_trait;
 use serde::Deserialize;
-use spinners::{Spinner, Spinners};
-use std::fmt::{Display, Formatter};
-use std::path::Path;
 
+/// Fabric's server launcher, built by the Fabric installer for a loader version.
 pub struct Fabric {
-    version: String,
-    loader: String,
-    installer: String,
+    http: Http,
+    base_url: String,
 }
 
 impl Fabric {
-    pub async fn new() -> Result<Self> {
-        let mut sp = Spinner::new(Spinners::Dots, "Downloading metadata".into());
-        let lists = join3(
-            Self::get_versions(),
-            Self::get_installers(),
-            Self::get_loaders(),
-        )
-        .await;
-        let (version_list, installer_list, loader_list) = (lists.0?, lists.1?, lists.2?);
-        sp.stop_and_persist("✔", "Finished downloading metadata".into());
-
-        let options = {
-            let mut out = version_list.versions;
-            if Confirm::new("Only stable versions?")
-                .with_default(true)
-                .prompt()?
-            {
-                out.retain(|v| v.stable);
-            }
-            out
-        };
-        let version = Select::new("Select version", options).prompt()?;
-
-        let options = {
-            let mut out = loader_list;
-            if Confirm::new("Only stable loaders?")
-                .with_default(true)
-                .prompt()?
-            {
-                out.retain(|l| l.stable);
-            }
-            out
-        };
-        let loader = Select::new("Select loader", options).prompt()?;
-
-        let options = {
-            let mut out = installer_list;
-            if Confirm::new("Only stable installers?")
-                .with_default(true)
-                .prompt()?
-            {
-                out.retain(|i| i.stable);
-            }
-            out
-        };
-        let installer = Select::new("Select installer", options).prompt()?;
-
-        Ok(Self {
-            installer: installer.version,
-            loader: loader.version,
-            version: version.version,
-        })
+    /// `base_url` is the base URL of Fabric's meta API.
+    pub fn new(http: Http, base_url: String) -> Self {
+        Self { http, base_url }
+    }
+
+    async fn get_versions(&self) -> Result<VersionList> {
+        let url = format!("{}/v2/versions/", self.base_url);
+        self.http.get_json(&url).await
     }
 
-    async fn get_versions() -> Result<VersionList> {
-        let url = "https://meta.fabricmc.net/v2/versions/";
-        let res = reqwest::get(url).await?.error_for_status()?;
-        let body = res.text().await?;
-        let ver = serde_json::from_str(&body)?;
-        Ok(ver)
+    async fn get_loaders(&self) -> Result<Vec<LoaderInfo>> {
+        let url = format!("{}/v2/versions/loader", self.base_url);
+        self.http.get_json(&url).await
     }
 
-    async fn get_loaders() -> Result<Vec<LoaderInfo>> {
-        let url = "https://meta.fabricmc.net/v2/versions/loader";
-        let res = reqwest::get(url).await?.error_for_status()?;
-        let body = res.text().await?;
-        let ver = serde_json::from_str(&body)?;
-        Ok(ver)
+    async fn get_installers(&self) -> Result<Vec<InstallerInfo>> {
+        let url = format!("{}/v2/versions/installer", self.base_url);
+        self.http.get_json(&url).await
+    }
+}
+
+/// Builds are Fabric loader versions. The server launcher is built with the latest stable
+/// installer unless another one is picked with [`Provider::resolve_with_installer`].
+#[async_trait]
+impl Provider for Fabric {
+    fn http(&self) -> &Http {
+        &self.http
     }
 
-    async fn get_installers() -> Result<Vec<InstallerInfo>> {
-        let url = "https://meta.fabricmc.net/v2/versions/installer";
-        let res = reqwest::get(url).await?.error_for_status()?;
-        let body = res.text().await?;
-        let ver = serde_json::from_str(&body)?;
-        Ok(ver)
+    async fn versions(&self) -> Result<Vec<Version>> {
+        let version_list = self.get_versions().await?;
+
+        Ok(version_list
+            .versions
+            .into_iter()
+            .map(|v| Version {
+                id: v.version,
+                stable: v.stable,
+                support: None,
+            })
+            .collect())
     }
 
-    pub async fn install(&self, path: &Path) -> Result<()> {
+    async fn builds(&self, _version: &str) -> Result<Vec<Build>> {
+        let loader_list = self.get_loaders().await?;
+
+        Ok(loader_list
+            .into_iter()
+            .map(|l| Build {
+                id: l.version,
+                channel: match l.stable {
+                    true => Channel::Default,
+                    false => Channel::Experimental,
+                },
+                time: None,
+                changes: Vec::new(),
+                checksum: None,
+            })
+            .collect())
+    }
+
+    async fn resolve(&self, version: &str, build: &str) -> Result<Artifact> {
+        let installer = self
+            .get_installers()
+            .await?
+            .into_iter()
+            .find(|i| i.stable)
+            .ok_or_else(|| Error::Other("no stable Fabric installer available".to_string()))?;
+
+        self.resolve_with_installer(version, build, &installer.version)
+            .await
+    }
+
+    async fn installers(&self) -> Result<Vec<Version>> {
+        Ok(self
+            .get_installers()
+            .await?
+            .into_iter()
+            .map(|i| Version {
+                id: i.version,
+                stable: i.stable,
+                support: None,
+            })
+            .collect())
+    }
+
+    async fn resolve_with_installer(
+        &self,
+        version: &str,
+        build: &str,
+        installer: &str,
+    ) -> Result<Artifact> {
         let url = format!(
-            "https://meta.fabricmc.net/v2/versions/loader/{}/{}/{}/server/jar",
-            self.version, self.loader, self.installer
+            "{}/v2/versions/loader/{}/{}/{}/server/jar",
+            self.base_url, version, build, installer
         );
-        let content = download_file(&url, "server.jar").await?;
-
-        install_server_jar(path, &content).await?;
 
-        Ok(())
+        Ok(Artifact {
+            distribution: Distribution::Fabric,
+            version: version.to_string(),
+            build: build.to_string(),
+            url,
+            file_name: format!(
+                "fabric-server-mc.{}-loader.{}-launcher.{}.jar",
+                version, build, installer
+            ),
+            checksum: None,
+            java: None,
+            installer: Some(installer.to_string()),
+        })
     }
 }
 
@@ -117,44 +136,14 @@ struct VersionInfo {
     stable: bool,
 }
 
-impl Display for VersionInfo {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let stable = match self.stable {
-            true => "stable",
-            false => "unstable",
-        };
-        write!(f, "{} - {}", self.version, stable)
-    }
-}
-
 #[derive(Deserialize, Clone)]
 struct LoaderInfo {
     version: String,
     stable: bool,
 }
 
-impl Display for LoaderInfo {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let stable = match self.stable {
-            true => "stable",
-            false => "unstable",
-        };
-        write!(f, "{} - {}", self.version, stable)
-    }
-}
-
 #[derive(Deserialize, Clone)]
 struct InstallerInfo {
     version: String,
     stable: bool,
 }
-
-impl Display for InstallerInfo {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let stable = match self.stable {
-            true => "stable",
-            false => "unstable",
-        };
-        write!(f, "{} - {}", self.version, stable)
-    }
-}
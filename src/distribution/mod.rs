@@ -1,70 +1,489 @@
+//! Server distributions and what they have in common.
+//!
+//! Every [`Distribution`] has a [`Provider`] that lists its versions and builds, resolves a
+//! build to an [`Artifact`] and installs it.
+
+use crate::cache::{copy_file, Cache};
+use crate::config::ApiConfig;
 use crate::error::*;
-use bytes::Bytes;
-use futures_util::StreamExt;
-use indicatif::{ProgressBar, ProgressStyle};
-use serde::Deserialize;
-use spinners::{Spinner, Spinners};
+use crate::http::Http;
+use async_trait::async_trait;
+use clap::ValueEnum;
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::fmt::{Display, Formatter};
 use std::fs;
-use std::path::Path;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use strum::{Display, EnumIter};
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 
+pub use buildtools::{BuildToolsOptions, Compile};
 pub use fabric::Fabric;
-pub use folia::Folia;
-pub use paper::Paper;
+pub use papermc::PaperMc;
 pub use purpur::Purpur;
 pub use spigot::Spigot;
 pub use vanilla::Vanilla;
-pub use velocity::Velocity;
 
+mod buildtools;
 mod fabric;
-mod folia;
-mod paper;
+mod papermc;
 mod purpur;
 mod spigot;
 mod vanilla;
-mod velocity;
 
-#[derive(Debug, Display, Deserialize, EnumIter, Copy, Clone)]
+/// Supported server distributions.
+#[derive(
+    Debug, Display, Serialize, Deserialize, EnumIter, ValueEnum, Copy, Clone, PartialEq, Eq,
+)]
+#[serde(rename_all = "lowercase")]
 pub enum Distribution {
+    /// Paper by PaperMC.
     Paper,
+    /// Purpur, a fork of Paper.
     Purpur,
+    /// Velocity, PaperMC's proxy.
     Velocity,
+    /// Folia, PaperMC's regionized fork of Paper.
     Folia,
+    /// Spigot, built locally with BuildTools.
     Spigot,
+    /// CraftBukkit, built locally with BuildTools like Spigot.
+    #[value(name = "craftbukkit")]
+    CraftBukkit,
+    /// Fabric's server launcher with the Fabric loader.
     Fabric,
+    /// Mojang's server.
     Vanilla,
 }
 
-pub async fn download_file(url: &str, message: &str) -> Result<Bytes> {
-    let req = reqwest::get(url).await?.error_for_status()?;
+impl Distribution {
+    /// Provider used to list, resolve and install versions of this distribution.
+    pub fn provider(&self, http: &Http, api: &ApiConfig) -> Box<dyn Provider> {
+        let http = http.clone();
+        match self {
+            Distribution::Paper | Distribution::Folia | Distribution::Velocity => Box::new(
+                PaperMc::new(http, *self, base_url(&api.fill), base_url(&api.papermc)),
+            ),
+            Distribution::Purpur => Box::new(Purpur::new(http, base_url(&api.purpur))),
+            Distribution::Spigot | Distribution::CraftBukkit => {
+                Box::new(Spigot::new(http, *self, base_url(&api.spigot)))
+            }
+            Distribution::Fabric => Box::new(Fabric::new(http, base_url(&api.fabric))),
+            Distribution::Vanilla => Box::new(Vanilla::new(http, base_url(&api.mojang))),
+        }
+    }
+}
+
+fn base_url(url: &str) -> String {
+    url.trim_end_matches('/').to_string()
+}
+
+/// Common interface of all server distributions.
+///
+/// Versions and builds are listed newest first.
+#[async_trait]
+pub trait Provider: Send + Sync {
+    /// Client used for all requests of the provider.
+    fn http(&self) -> &Http;
+
+    /// Available versions.
+    async fn versions(&self) -> Result<Vec<Version>>;
+
+    /// Available builds of `version`.
+    async fn builds(&self, version: &str) -> Result<Vec<Build>>;
+
+    /// Resolves a version and build id to a downloadable artifact.
+    async fn resolve(&self, version: &str, build: &str) -> Result<Artifact>;
+
+    /// Installer versions the server jar can be built with, newest first. Only Fabric's server
+    /// launcher is built by an installer, other distributions have none.
+    async fn installers(&self) -> Result<Vec<Version>> {
+        Ok(Vec::new())
+    }
+
+    /// Like [`Provider::resolve`], but builds the server jar with the installer version
+    /// `installer`.
+    async fn resolve_with_installer(
+        &self,
+        _version: &str,
+        _build: &str,
+        installer: &str,
+    ) -> Result<Artifact> {
+        Err(Error::InvalidArgument(format!(
+            "can't use installer {}, only Fabric has installer versions",
+            installer
+        )))
+    }
+
+    /// Installs the artifact as `server.jar` into `dir`.
+    async fn install(
+        &self,
+        artifact: &Artifact,
+        dir: &Path,
+        options: &InstallOptions,
+    ) -> Result<()> {
+        install_server_jar(self.http(), dir, artifact, options).await
+    }
+}
+
+/// A Minecraft (or Velocity) version.
+#[derive(Debug, Clone)]
+pub struct Version {
+    /// Version id, e.g. `1.21.1`.
+    pub id: String,
+    /// Whether it is a release, as opposed to a snapshot or pre-release.
+    pub stable: bool,
+    /// Whether the distribution still supports the version, if it publishes that.
+    pub support: Option<Support>,
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)?;
+
+        let mut flags = Vec::new();
+        if !self.stable {
+            flags.push("unstable".to_string());
+        }
+        match self.support {
+            Some(Support::Supported) | None => {}
+            Some(support) => flags.push(support.to_string()),
+        }
+        if !flags.is_empty() {
+            write!(f, " - {}", flags.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Support status of a version.
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq)]
+pub enum Support {
+    /// Receives updates.
+    #[strum(serialize = "supported")]
+    Supported,
+    /// No longer receives updates.
+    #[strum(serialize = "end-of-life")]
+    EndOfLife,
+    /// Not supported by the distribution.
+    #[strum(serialize = "unsupported")]
+    Unsupported,
+}
+
+/// A build of a version, e.g. a Paper build number or a Fabric loader version.
+#[derive(Debug, Clone, Serialize)]
+pub struct Build {
+    /// Build id, e.g. `132`.
+    pub id: String,
+    /// Channel the build was published on.
+    pub channel: Channel,
+    /// When the build was published, as `YYYY-MM-DD HH:MM` in UTC, if the API says.
+    pub time: Option<String>,
+    /// Commits included in the build, empty if the API doesn't list them.
+    pub changes: Vec<Change>,
+    /// Checksum of the server jar, if the build list publishes it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<Checksum>,
+}
+
+impl Display for Build {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} - {}", self.id, self.channel)?;
+        if let Some(time) = &self.time {
+            write!(f, " - {}", time)?;
+        }
+        if let Some(change) = self.changes.first() {
+            write!(f, " - {}", change.summary)?;
+            if self.changes.len() > 1 {
+                write!(f, " (+{} more)", self.changes.len() - 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A commit included in a build.
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    /// Commit hash.
+    pub commit: String,
+    /// First line of the commit message.
+    pub summary: String,
+}
+
+/// Formats an ISO 8601 timestamp like `2024-08-30T12:34:56.789Z` as `2024-08-30 12:34`.
+pub(crate) fn format_iso_time(time: &str) -> String {
+    time.get(..16).unwrap_or(time).replacen('T', " ", 1)
+}
 
-    if let Some(file_size) = req.content_length() {
-        let pb = ProgressBar::new(file_size);
-        pb.set_style(ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-            .unwrap()
-            .progress_chars("=> "));
+/// Formats milliseconds since the Unix epoch as `2024-08-30 12:34` in UTC.
+pub(crate) fn format_unix_millis(millis: i64) -> String {
+    let secs = millis.div_euclid(1000);
+    let (days, secs) = (secs.div_euclid(86400), secs.rem_euclid(86400));
 
-        let mut content = Vec::with_capacity(file_size as usize);
+    // days since 1970-01-01 to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
 
-        let mut stream = req.bytes_stream();
-        while let Some(chunk) = stream.next().await {
-            let bytes = chunk?.to_vec();
-            pb.inc(bytes.len() as u64);
-            content.extend(bytes);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60
+    )
+}
+
+/// Release channel of a build.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Display, Copy, Clone, Hash)]
+pub enum Channel {
+    /// Builds that may be unstable.
+    #[serde(rename = "experimental")]
+    Experimental,
+    /// Builds recommended for production.
+    #[serde(rename = "default")]
+    Default,
+}
+
+impl FromStr for Channel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "default" | "stable" => Ok(Channel::Default),
+            "experimental" | "unstable" => Ok(Channel::Experimental),
+            _ => Err(Error::InvalidArgument(format!("unknown channel {}", s))),
         }
-        pb.finish();
-        Ok(Bytes::from(content))
-    } else {
-        let mut sp = Spinner::new(Spinners::Dots, format!("Downloading {}", message));
-        let content = req.bytes().await?;
-        sp.stop_and_persist("✔", format!("Finished downloading {}", message));
+    }
+}
 
-        Ok(content)
+/// A resolved, downloadable server jar (or in case of Spigot and CraftBukkit, the BuildTools jar).
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    /// Distribution the jar belongs to.
+    pub distribution: Distribution,
+    /// Version id, e.g. `1.21.1`.
+    pub version: String,
+    /// Build id, see [`Build::id`].
+    pub build: String,
+    /// Download URL.
+    pub url: String,
+    /// File name the API gives the jar.
+    pub file_name: String,
+    /// Checksum published by the API, if any.
+    pub checksum: Option<Checksum>,
+    /// Java versions the server runs on, if the API says.
+    pub java: Option<JavaRequirement>,
+    /// Installer version the Fabric server launcher is built with.
+    pub installer: Option<String>,
+}
+
+/// Hex encoded checksum published by the distribution's API.
+///
+/// Serialized in its display form, e.g. `sha256:…`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Checksum {
+    /// SHA-256, used by PaperMC.
+    Sha256(String),
+    /// SHA-1, used by Mojang.
+    Sha1(String),
+    /// MD5, used by Purpur.
+    Md5(String),
+}
+
+impl Checksum {
+    /// Hex encoded digest.
+    pub fn value(&self) -> &str {
+        match self {
+            Checksum::Sha256(hash) | Checksum::Sha1(hash) | Checksum::Md5(hash) => hash,
+        }
+    }
+
+    /// Whether the file at `path` has this checksum.
+    pub fn matches_file(&self, path: &Path) -> Result<bool> {
+        let mut hasher = self.hasher();
+        let mut file = fs::File::open(path)?;
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let n = file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+
+        Ok(hasher.finalize().eq_ignore_ascii_case(self.value()))
+    }
+
+    pub(crate) fn hasher(&self) -> Hasher {
+        match self {
+            Checksum::Sha256(_) => Hasher::Sha256(Sha256::new()),
+            Checksum::Sha1(_) => Hasher::Sha1(Sha1::new()),
+            Checksum::Md5(_) => Hasher::Md5(Md5::new()),
+        }
     }
 }
 
+/// Incrementally hashes a download with the algorithm of its [`Checksum`].
+pub(crate) enum Hasher {
+    Sha256(Sha256),
+    Sha1(Sha1),
+    Md5(Md5),
+}
+
+impl Hasher {
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Sha1(hasher) => hasher.update(data),
+            Hasher::Md5(hasher) => hasher.update(data),
+        }
+    }
+
+    /// Hex encoded digest.
+    pub(crate) fn finalize(self) -> String {
+        match self {
+            Hasher::Sha256(hasher) => hex::encode(hasher.finalize()),
+            Hasher::Sha1(hasher) => hex::encode(hasher.finalize()),
+            Hasher::Md5(hasher) => hex::encode(hasher.finalize()),
+        }
+    }
+}
+
+impl FromStr for Checksum {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once(':') {
+            Some(("sha256", hash)) => Ok(Checksum::Sha256(hash.to_string())),
+            Some(("sha1", hash)) => Ok(Checksum::Sha1(hash.to_string())),
+            Some(("md5", hash)) => Ok(Checksum::Md5(hash.to_string())),
+            _ => Err(Error::InvalidArgument(format!("invalid checksum {}", s))),
+        }
+    }
+}
+
+impl TryFrom<String> for Checksum {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<Checksum> for String {
+    fn from(value: Checksum) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for Checksum {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Checksum::Sha256(hash) => write!(f, "sha256:{}", hash),
+            Checksum::Sha1(hash) => write!(f, "sha1:{}", hash),
+            Checksum::Md5(hash) => write!(f, "md5:{}", hash),
+        }
+    }
+}
+
+/// Options for [`Provider::install`].
+#[derive(Debug, Clone, Default)]
+pub struct InstallOptions {
+    /// Java used to run the server and BuildTools.
+    pub java_path: PathBuf,
+    /// Install even if the download doesn't match its published checksum.
+    /// Unverified downloads bypass the cache.
+    pub skip_verify: bool,
+    /// Reuse and store downloads in this cache. Downloads without a published checksum, like
+    /// Fabric jars and BuildTools, are cached as downloaded.
+    pub cache: Option<Cache>,
+    /// Initial and maximum heap size written to the start script, e.g. `4G`.
+    pub memory: Option<String>,
+    /// Keep the temporary BuildTools workspace of a Spigot build that didn't use the cached one.
+    pub keep_build_cache: bool,
+    /// Options for BuildTools when building Spigot.
+    pub build_tools: BuildToolsOptions,
+}
+
+impl InstallOptions {
+    /// The cache to use, none if downloads aren't verified.
+    pub(crate) fn verified_cache(&self) -> Option<&Cache> {
+        self.cache.as_ref().filter(|_| !self.skip_verify)
+    }
+}
+
+/// Supported Java major versions (e.g. 21).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JavaRequirement {
+    /// Oldest supported major version.
+    pub min: u32,
+    /// Newest supported major version, unbounded if `None`.
+    pub max: Option<u32>,
+}
+
+impl JavaRequirement {
+    /// Whether Java `major` is supported.
+    pub fn allows(&self, major: u32) -> bool {
+        major >= self.min && self.max.is_none_or(|max| major <= max)
+    }
+}
+
+impl Display for JavaRequirement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) => write!(f, "Java {} to {}", self.min, max),
+            None => write!(f, "Java {} or newer", self.min),
+        }
+    }
+}
+
+/// Downloads `artifact` to `server.jar` in `path`, or copies it from the cache.
+pub async fn install_server_jar(
+    http: &Http,
+    path: &Path,
+    artifact: &Artifact,
+    options: &InstallOptions,
+) -> Result<()> {
+    fs::create_dir_all(path)?;
+    let server_jar = path.join("server.jar");
+
+    match options.verified_cache() {
+        Some(cache) => {
+            let cached = cache
+                .fetch(
+                    http,
+                    &Cache::artifact_key(artifact),
+                    &artifact.url,
+                    artifact.checksum.as_ref(),
+                    "server.jar",
+                )
+                .await?;
+            copy_file(&cached, &server_jar).await
+        }
+        None => {
+            let checksum = artifact.checksum.as_ref().filter(|_| !options.skip_verify);
+            http.download(&artifact.url, &server_jar, checksum, "server.jar")
+                .await
+        }
+    }
+}
+
+/// Writes an `eula.txt` accepting the Minecraft EULA.
 pub async fn install_eula(path: &Path) -> Result<()> {
     fs::create_dir_all(path)?;
 
@@ -77,50 +496,111 @@ pub async fn install_eula(path: &Path) -> Result<()> {
     Ok(())
 }
 
-pub async fn install_server_jar(path: &Path, bytes: &Bytes) -> Result<()> {
-    fs::create_dir_all(path)?;
+/// Whether the `eula.txt` in `path` accepts the Minecraft EULA.
+pub fn eula_accepted(path: &Path) -> bool {
+    fs::read_to_string(path.join("eula.txt"))
+        .map(|content| content.lines().any(|l| l.trim() == "eula=true"))
+        .unwrap_or(false)
+}
 
-    let mut path = path.to_owned();
-    path.push("server.jar");
+/// File name of the start script on this platform.
+#[cfg(windows)]
+pub const START_SCRIPT: &str = "start.bat";
+/// File name of the start script on this platform.
+#[cfg(not(windows))]
+pub const START_SCRIPT: &str = "start.sh";
 
-    let mut server_jar = File::create(&path).await?;
-    server_jar.write_all(bytes).await?;
+/// Content of the start script running `server.jar` with `java_path` and `memory`
+/// (e.g. `4G`) as initial and maximum heap size.
+pub fn start_script(java_path: &Path, memory: Option<&str>) -> Result<String> {
+    let mut command = quote_java_path(java_path)?;
+    if let Some(memory) = memory {
+        validate_memory(memory)?;
+        command.push_str(&format!(" -Xms{0} -Xmx{0}", memory));
+    }
+    command.push_str(" -jar server.jar");
 
-    Ok(())
+    match cfg!(windows) {
+        true => Ok(command),
+        false => Ok(format!("#!/usr/bin/env sh\n{}", command)),
+    }
 }
 
+/// Quotes the Java path for the start script. It comes from `automc.toml` and `automc.lock`,
+/// so it must never be interpreted as anything but a path.
 #[cfg(windows)]
-pub async fn install_start_script(path: &Path, java_path: &Path) -> Result<()> {
+fn quote_java_path(java_path: &Path) -> Result<String> {
+    let path = java_path.display().to_string();
+    // cmd expands `%` even in quotes and has no way to escape `"`
+    if path.contains(['%', '"', '&']) {
+        return Err(Error::InvalidArgument(format!(
+            "invalid Java path {}, it can't contain %, \" or &",
+            path
+        )));
+    }
+    Ok(format!("\"{}\"", path))
+}
+
+#[cfg(not(windows))]
+fn quote_java_path(java_path: &Path) -> Result<String> {
+    // nothing is expanded in single quotes, a `'` is closed, escaped and reopened
+    let path = java_path.display().to_string().replace('\'', r"'\''");
+    Ok(format!("'{}'", path))
+}
+
+/// Checks that `memory` is a heap size like `4G` or `512M`. It is written into the start
+/// script as is, so anything else could run arbitrary commands.
+pub fn validate_memory(memory: &str) -> Result<()> {
+    let digits = memory.trim_end_matches(['K', 'k', 'M', 'm', 'G', 'g']);
+    let valid = !digits.is_empty()
+        && memory.len() - digits.len() <= 1
+        && digits.chars().all(|c| c.is_ascii_digit());
+
+    match valid {
+        true => Ok(()),
+        false => Err(Error::InvalidArgument(format!(
+            "invalid memory {}, expected a size like 4G or 512M",
+            memory
+        ))),
+    }
+}
+
+/// Writes the [`start_script`] into `path`.
+#[cfg(windows)]
+pub async fn install_start_script(
+    path: &Path,
+    java_path: &Path,
+    memory: Option<&str>,
+) -> Result<()> {
+    let script = start_script(java_path, memory)?;
     fs::create_dir_all(path)?;
 
     let mut path = path.to_owned();
-    path.push("start.bat");
+    path.push(START_SCRIPT);
 
     let mut file = File::create(path).await?;
-    file.write_all(format!("{:?} -jar server.jar", java_path.display()).as_bytes())
-        .await?;
+    file.write_all(script.as_bytes()).await?;
 
     Ok(())
 }
 
+/// Writes the [`start_script`] into `path` and makes it executable.
 #[cfg(unix)]
-pub async fn install_start_script(path: &Path, java_path: &Path) -> Result<()> {
+pub async fn install_start_script(
+    path: &Path,
+    java_path: &Path,
+    memory: Option<&str>,
+) -> Result<()> {
     use std::os::unix::fs::PermissionsExt;
 
+    let script = start_script(java_path, memory)?;
     fs::create_dir_all(path)?;
 
     let mut path = path.to_owned();
-    path.push("start.sh");
+    path.push(START_SCRIPT);
 
     let mut file = File::create(&path).await?;
-    file.write_all(
-        format!(
-            "#!/usr/bin/env sh\n{:?} -jar server.jar",
-            java_path.display()
-        )
-        .as_bytes(),
-    )
-    .await?;
+    file.write_all(script.as_bytes()).await?;
 
     let mut perms = file.metadata().await?.permissions();
     perms.set_mode(0o755); // same as chmod +x
@@ -129,7 +609,12 @@ pub async fn install_start_script(path: &Path, java_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Start scripts are only supported on unix and Windows.
 #[cfg(all(not(unix), not(windows)))]
-pub async fn install_start_script(path: &Path, java_path: &Path) -> Result<()> {
+pub async fn install_start_script(
+    path: &Path,
+    java_path: &Path,
+    memory: Option<&str>,
+) -> Result<()> {
     Err(Error::Other("unsupported OS".to_string()))
 }
@@ -0,0 +1,414 @@
+use crate::distribution::{
+    format_iso_time, Artifact, Build, Change, Checksum, Distribution, JavaRequirement, Provider,
+    Version,
+};
+use crate::error::*;
+use crate::http::Http;
+use crate::version::MinecraftVersion;
+use async_trait::async_trait;
+use std::cmp::{Ordering, Reverse};
+
+/// Paper, Folia and Velocity, which are all published by PaperMC.
+///
+/// Uses the Fill (v3) API, falling back to the v2 API if it can't be reached.
+pub struct PaperMc {
+    http: Http,
+    distribution: Distribution,
+    fill_url: String,
+    api_url: String,
+}
+
+impl PaperMc {
+    /// `distribution` has to be Paper, Folia or Velocity. `fill_url` is the base URL of the
+    /// Fill (v3) API and `api_url` the one of the v2 API.
+    pub fn new(http: Http, distribution: Distribution, fill_url: String, api_url: String) -> Self {
+        Self {
+            http,
+            distribution,
+            fill_url,
+            api_url,
+        }
+    }
+
+    fn project(&self) -> &'static str {
+        match self.distribution {
+            Distribution::Folia => "folia",
+            Distribution::Velocity => "velocity",
+            _ => "paper",
+        }
+    }
+
+    /// Pre-releases have a suffix like `-pre1`, except for Velocity, whose versions are all
+    /// `-SNAPSHOT`s.
+    fn is_stable(&self, version: &str) -> bool {
+        self.distribution == Distribution::Velocity || !version.contains('-')
+    }
+
+    async fn get_versions_v3(&self) -> Result<v3::VersionList> {
+        let url = format!("{}/v3/projects/{}/versions", self.fill_url, self.project());
+        self.http.get_json(&url).await
+    }
+
+    async fn get_version_v3(&self, version: &str) -> Result<v3::VersionEntry> {
+        let url = format!(
+            "{}/v3/projects/{}/versions/{}",
+            self.fill_url,
+            self.project(),
+            version
+        );
+        self.http.get_json(&url).await
+    }
+
+    async fn get_builds_v3(&self, version: &str) -> Result<Vec<v3::BuildInfo>> {
+        let url = format!(
+            "{}/v3/projects/{}/versions/{}/builds",
+            self.fill_url,
+            self.project(),
+            version
+        );
+        let mut builds: Vec<v3::BuildInfo> = self.http.get_json(&url).await?;
+        builds.sort_by_key(|b| Reverse(b.id));
+        Ok(builds)
+    }
+
+    async fn get_build_v3(&self, version: &str, build: &str) -> Result<v3::BuildInfo> {
+        let url = format!(
+            "{}/v3/projects/{}/versions/{}/builds/{}",
+            self.fill_url,
+            self.project(),
+            version,
+            build
+        );
+        self.http.get_json(&url).await
+    }
+
+    async fn get_versions_v2(&self) -> Result<v2::VersionList> {
+        let url = format!("{}/v2/projects/{}", self.api_url, self.project());
+        self.http.get_json(&url).await
+    }
+
+    async fn get_builds_v2(&self, version: &str) -> Result<v2::BuildList> {
+        let url = format!(
+            "{}/v2/projects/{}/versions/{}/builds",
+            self.api_url,
+            self.project(),
+            version
+        );
+        self.http.get_json(&url).await
+    }
+
+    async fn versions_v3(&self) -> Result<Vec<Version>> {
+        let mut versions: Vec<Version> = self
+            .get_versions_v3()
+            .await?
+            .versions
+            .into_iter()
+            .map(|entry| Version {
+                stable: self.is_stable(&entry.version.id),
+                support: entry.version.support.and_then(|s| s.support()),
+                id: entry.version.id,
+            })
+            .collect();
+
+        // newest first, the API groups versions by family
+        versions.sort_by(|a, b| {
+            let a = a.id.parse::<MinecraftVersion>().ok();
+            let b = b.id.parse::<MinecraftVersion>().ok();
+            b.partial_cmp(&a).unwrap_or(Ordering::Equal)
+        });
+        Ok(versions)
+    }
+
+    async fn versions_v2(&self) -> Result<Vec<Version>> {
+        Ok(self
+            .get_versions_v2()
+            .await?
+            .versions
+            .into_iter()
+            .rev()
+            .map(|id| Version {
+                stable: self.is_stable(&id),
+                support: None,
+                id,
+            })
+            .collect())
+    }
+
+    async fn builds_v3(&self, version: &str) -> Result<Vec<Build>> {
+        Ok(self
+            .get_builds_v3(version)
+            .await?
+            .into_iter()
+            .map(|b| Build {
+                id: b.id.to_string(),
+                checksum: b
+                    .downloads
+                    .server
+                    .as_ref()
+                    .map(|d| Checksum::Sha256(d.checksums.sha256.clone())),
+                channel: b.channel(),
+                time: b.time.as_deref().map(format_iso_time),
+                changes: b
+                    .commits
+                    .into_iter()
+                    .map(|c| Change {
+                        summary: c.message.lines().next().unwrap_or("").to_string(),
+                        commit: c.sha,
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    async fn builds_v2(&self, version: &str) -> Result<Vec<Build>> {
+        Ok(self
+            .get_builds_v2(version)
+            .await?
+            .builds
+            .into_iter()
+            .rev()
+            .map(|b| Build {
+                id: b.build_id.to_string(),
+                checksum: Some(Checksum::Sha256(b.downloads.application.sha256)),
+                channel: b.channel,
+                time: Some(format_iso_time(&b.time)),
+                changes: b
+                    .changes
+                    .into_iter()
+                    .map(|c| Change {
+                        commit: c.commit,
+                        summary: c.summary,
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    async fn resolve_v3(&self, version: &str, build: &str) -> Result<Artifact> {
+        let info = self.get_build_v3(version, build).await?;
+        let download = info.downloads.server.ok_or_else(|| {
+            Error::Other(format!(
+                "{} {} build {} has no server download",
+                self.distribution, version, build
+            ))
+        })?;
+        // the Java requirement is optional, a missing one doesn't prevent installing
+        let java = self.get_version_v3(version).await.ok().and_then(|entry| {
+            let minimum = entry.version.java?.version?.minimum;
+            Some(JavaRequirement {
+                min: minimum,
+                max: None,
+            })
+        });
+
+        Ok(Artifact {
+            distribution: self.distribution,
+            version: version.to_string(),
+            build: build.to_string(),
+            url: download.url,
+            file_name: download.name,
+            checksum: Some(Checksum::Sha256(download.checksums.sha256)),
+            java,
+            installer: None,
+        })
+    }
+
+    async fn resolve_v2(&self, version: &str, build: &str) -> Result<Artifact> {
+        let info = self
+            .get_builds_v2(version)
+            .await?
+            .builds
+            .into_iter()
+            .find(|b| b.build_id.to_string() == build)
+            .ok_or_else(|| Error::InvalidArgument(format!("unknown build {}", build)))?;
+
+        let jar_name = info.downloads.application.name;
+        let url = format!(
+            "{}/v2/projects/{}/versions/{}/builds/{}/downloads/{}",
+            self.api_url,
+            self.project(),
+            version,
+            build,
+            jar_name
+        );
+
+        Ok(Artifact {
+            distribution: self.distribution,
+            version: version.to_string(),
+            build: build.to_string(),
+            url,
+            file_name: jar_name,
+            checksum: Some(Checksum::Sha256(info.downloads.application.sha256)),
+            java: None,
+            installer: None,
+        })
+    }
+}
+
+/// Returns `v3` if it succeeded, otherwise tries `v2`. If both fail, the v3 error is returned.
+async fn with_fallback<T>(
+    v3: Result<T>,
+    v2: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    match v3 {
+        Ok(value) => Ok(value),
+        Err(e) => v2.await.map_err(|_| e),
+    }
+}
+
+#[async_trait]
+impl Provider for PaperMc {
+    fn http(&self) -> &Http {
+        &self.http
+    }
+
+    async fn versions(&self) -> Result<Vec<Version>> {
+        with_fallback(self.versions_v3().await, self.versions_v2()).await
+    }
+
+    async fn builds(&self, version: &str) -> Result<Vec<Build>> {
+        with_fallback(self.builds_v3(version).await, self.builds_v2(version)).await
+    }
+
+    async fn resolve(&self, version: &str, build: &str) -> Result<Artifact> {
+        with_fallback(
+            self.resolve_v3(version, build).await,
+            self.resolve_v2(version, build),
+        )
+        .await
+    }
+}
+
+mod v3 {
+    use crate::distribution::{Channel, Support};
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug)]
+    pub struct VersionList {
+        pub versions: Vec<VersionEntry>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct VersionEntry {
+        pub version: VersionInfo,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct VersionInfo {
+        pub id: String,
+        pub support: Option<SupportInfo>,
+        pub java: Option<JavaInfo>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct SupportInfo {
+        status: String,
+    }
+
+    impl SupportInfo {
+        pub fn support(&self) -> Option<Support> {
+            match self.status.as_str() {
+                "SUPPORTED" => Some(Support::Supported),
+                "DEPRECATED" | "END_OF_LIFE" => Some(Support::EndOfLife),
+                "UNSUPPORTED" => Some(Support::Unsupported),
+                _ => None,
+            }
+        }
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct JavaInfo {
+        pub version: Option<JavaVersion>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct JavaVersion {
+        pub minimum: u32,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct BuildInfo {
+        pub id: i64,
+        pub time: Option<String>,
+        channel: String,
+        #[serde(default)]
+        pub commits: Vec<CommitInfo>,
+        pub downloads: Downloads,
+    }
+
+    impl BuildInfo {
+        pub fn channel(&self) -> Channel {
+            match self.channel.as_str() {
+                "ALPHA" | "BETA" => Channel::Experimental,
+                _ => Channel::Default,
+            }
+        }
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct CommitInfo {
+        pub sha: String,
+        pub message: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Downloads {
+        #[serde(rename = "server:default")]
+        pub server: Option<Download>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Download {
+        pub name: String,
+        pub checksums: Checksums,
+        pub url: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Checksums {
+        pub sha256: String,
+    }
+}
+
+mod v2 {
+    use crate::distribution::Channel;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    pub struct VersionList {
+        pub versions: Vec<String>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct BuildList {
+        pub builds: Vec<BuildInfo>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct BuildInfo {
+        #[serde(rename = "build")]
+        pub build_id: i64,
+        pub channel: Channel,
+        pub time: String,
+        #[serde(default)]
+        pub changes: Vec<ChangeInfo>,
+        pub downloads: Downloads,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct ChangeInfo {
+        pub commit: String,
+        pub summary: String,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Downloads {
+        pub application: Download,
+    }
+
+    #[derive(Deserialize, Debug)]
+    pub struct Download {
+        pub name: String,
+        pub sha256: String,
+    }
+}
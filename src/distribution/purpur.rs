@@ -1,45 +1,105 @@
-use crate::distribution::{download_file, install_server_jar};
+use crate::distribution::{
+    format_unix_millis, Artifact, Build, Change, Channel, Checksum, Distribution, Provider, Version,
+};
 use crate::error::*;
-use inquire::Select;
+use crate::http::Http;
+use async_trait::async_trait;
 use serde::Deserialize;
-use spinners::{Spinner, Spinners};
-use std::path::Path;
 
+/// Purpur, published by PurpurMC.
 pub struct Purpur {
-    version: String,
+    http: Http,
+    base_url: String,
 }
 
 impl Purpur {
-    pub async fn new() -> Result<Self> {
-        let mut sp = Spinner::new(Spinners::Dots, "Downloading metadata".into());
-        let version_list = Self::get_versions().await?;
-        sp.stop_and_persist("✔", "Finished downloading metadata".into());
+    /// `base_url` is the base URL of Purpur's API.
+    pub fn new(http: Http, base_url: String) -> Self {
+        Self { http, base_url }
+    }
 
-        let mut options = version_list.versions;
-        options.reverse();
-        let version = Select::new("Select version", options).prompt()?;
+    async fn get_versions(&self) -> Result<VersionList> {
+        let url = format!("{}/v2/purpur/", self.base_url);
+        self.http.get_json(&url).await
+    }
 
-        Ok(Self { version })
+    async fn get_builds(&self, version: &str) -> Result<BuildList> {
+        let url = format!("{}/v2/purpur/{}?detailed=true", self.base_url, version);
+        self.http.get_json(&url).await
     }
 
-    async fn get_versions() -> Result<VersionList> {
-        let url = "https://api.purpurmc.org/v2/purpur/";
-        let res = reqwest::get(url).await?.error_for_status()?;
-        let body = res.text().await?;
-        let ver = serde_json::from_str(&body)?;
-        Ok(ver)
+    async fn get_build(&self, version: &str, build: &str) -> Result<BuildInfo> {
+        let url = format!("{}/v2/purpur/{}/{}", self.base_url, version, build);
+        self.http.get_json(&url).await
     }
+}
+
+#[async_trait]
+impl Provider for Purpur {
+    fn http(&self) -> &Http {
+        &self.http
+    }
+
+    async fn versions(&self) -> Result<Vec<Version>> {
+        let version_list = self.get_versions().await?;
+
+        Ok(version_list
+            .versions
+            .into_iter()
+            .rev()
+            .map(|id| Version {
+                id,
+                stable: true,
+                support: None,
+            })
+            .collect())
+    }
+
+    async fn builds(&self, version: &str) -> Result<Vec<Build>> {
+        let build_list = self.get_builds(version).await?;
+
+        Ok(build_list
+            .builds
+            .all
+            .into_iter()
+            .rev()
+            // failed builds have no jar to download
+            .filter(|b| b.result.as_deref() != Some("FAILURE"))
+            .map(|b| Build {
+                checksum: b.md5.map(Checksum::Md5),
+                id: b.build,
+                channel: Channel::Default,
+                time: b.timestamp.map(format_unix_millis),
+                changes: b
+                    .commits
+                    .into_iter()
+                    .map(|c| Change {
+                        commit: c.hash,
+                        summary: c.description.lines().next().unwrap_or("").to_string(),
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    async fn resolve(&self, version: &str, build: &str) -> Result<Artifact> {
+        let info = self.get_build(version, build).await?;
 
-    pub async fn install(&self, path: &Path) -> Result<()> {
         let url = format!(
-            "https://api.purpurmc.org/v2/purpur/{}/latest/download",
-            self.version,
+            "{}/v2/purpur/{}/{}/download",
+            self.base_url, version, info.build
         );
-        let content = download_file(&url, "server.jar").await?;
-
-        install_server_jar(path, &content).await?;
 
-        Ok(())
+        Ok(Artifact {
+            distribution: Distribution::Purpur,
+            version: version.to_string(),
+            file_name: format!("purpur-{}-{}.jar", version, info.build),
+            build: info.build,
+            url,
+            checksum: info.md5.map(Checksum::Md5),
+            java: None,
+            installer: None,
+        })
     }
 }
 
@@ -47,3 +107,30 @@ impl Purpur {
 struct VersionList {
     versions: Vec<String>,
 }
+
+#[derive(Deserialize)]
+struct BuildList {
+    builds: Builds,
+}
+
+#[derive(Deserialize)]
+struct Builds {
+    all: Vec<BuildInfo>,
+}
+
+#[derive(Deserialize)]
+struct BuildInfo {
+    build: String,
+    md5: Option<String>,
+    result: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    timestamp: Option<i64>,
+    #[serde(default)]
+    commits: Vec<CommitInfo>,
+}
+
+#[derive(Deserialize)]
+struct CommitInfo {
+    hash: String,
+    description: String,
+}
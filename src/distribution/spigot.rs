@@ -1,159 +1,234 @@
+use crate::cache::copy_file;
+use crate::distribution::buildtools::{build_tools_key, BuildToolsOptions, Compile, Workspace};
+use crate::distribution::{
+    Artifact, Build, Channel, Distribution, InstallOptions, JavaRequirement, Provider, Version,
+};
 use crate::error::*;
-use bytes::Bytes;
-use futures_util::StreamExt;
-use indicatif::{ProgressBar, ProgressStyle};
-use inquire::Text;
-use spinners::{Spinner, Spinners};
+use crate::http::Http;
+use crate::java::java_major_version;
+use crate::version::MinecraftVersion;
+use async_trait::async_trait;
+use std::cmp::Ordering;
 use std::path::Path;
-use std::process::Command;
 use tokio::fs;
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
 
+/// Spigot and CraftBukkit, which are both built locally with BuildTools.
 pub struct Spigot {
-    version: String,
+    http: Http,
+    distribution: Distribution,
+    base_url: String,
 }
 
-impl Spigot {
-    pub async fn new() -> Result<Self> {
-        let version;
-        loop {
-            let ver = Text::new("Select version")
-                .with_help_message("eg. 1.21.1")
-                .prompt()?;
-
-            let mut sp = Spinner::new(Spinners::Dots, "Validating version".into());
-            if !Self::check_version(&ver).await? {
-                sp.stop_with_message("Version is invalid".to_string());
-                continue;
+/// Builds are BuildTools revisions as published on hub.spigotmc.org and the resolved artifact
+/// is the BuildTools jar.
+#[async_trait]
+impl Provider for Spigot {
+    fn http(&self) -> &Http {
+        &self.http
+    }
+
+    /// Versions listed in the version index on hub.spigotmc.org.
+    async fn versions(&self) -> Result<Vec<Version>> {
+        let url = format!("{}/versions/", self.base_url);
+        let index = self.http.get_text(&url).await?;
+
+        let mut versions = index
+            .split("href=\"")
+            .skip(1)
+            .filter_map(|link| link.split('"').next()?.strip_suffix(".json"))
+            // the index also lists BuildTools revisions by number, e.g. `4321.json`
+            .filter(|id| id.contains('.'))
+            .filter_map(|id| Some((id.to_string(), id.parse::<MinecraftVersion>().ok()?)))
+            .collect::<Vec<_>>();
+        versions.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        versions.dedup_by(|(a, _), (b, _)| a == b);
+
+        Ok(versions
+            .into_iter()
+            .map(|(id, parsed)| Version {
+                stable: !parsed.is_prerelease(),
+                support: None,
+                id,
+            })
+            .collect())
+    }
+
+    async fn builds(&self, version: &str) -> Result<Vec<Build>> {
+        let info = self.get_version_info(version).await?;
+
+        Ok(vec![Build {
+            id: gjson::get(&info, "name").to_string(),
+            channel: Channel::Default,
+            time: None,
+            changes: Vec::new(),
+            checksum: None,
+        }])
+    }
+
+    async fn resolve(&self, version: &str, build: &str) -> Result<Artifact> {
+        let info = self.get_version_info(version).await?;
+        // class file versions, e.g. [52, 65] means Java 8 to 21
+        let java = gjson::get(&info, "javaVersions");
+        let range = java.array();
+        let java = range.first().map(|min| JavaRequirement {
+            min: min.u32().saturating_sub(44),
+            max: range.get(1).map(|v| v.u32().saturating_sub(44)),
+        });
+
+        Ok(Artifact {
+            distribution: self.distribution,
+            version: version.to_string(),
+            build: build.to_string(),
+            url: self.get_build_tools_url().await?,
+            file_name: "BuildTools.jar".to_string(),
+            checksum: None,
+            java,
+            installer: None,
+        })
+    }
+
+    async fn install(
+        &self,
+        artifact: &Artifact,
+        path: &Path,
+        options: &InstallOptions,
+    ) -> Result<()> {
+        let build_tools = &self.build_tools_options(options);
+        let jar_name = build_tools.jar_name(&artifact.version);
+        // the final name only renames the jar, so builds are cached under the default name
+        let jar_key = [
+            "spigot".to_string(),
+            artifact.version.clone(),
+            artifact.build.clone(),
+            format!("{}-{}.jar", build_tools.compile(), artifact.version),
+        ];
+        let cache = options.verified_cache();
+
+        // a build of the same revision can be reused as is, unless BuildTools has to run for
+        // the remapped jars it installs into the local Maven repository
+        if let Some(cached) = cache
+            .filter(|_| !build_tools.remapped)
+            .and_then(|cache| cache.get(&jar_key))
+        {
+            println!("✔ Using cached {}", jar_name);
+            fs::create_dir_all(path).await?;
+            return copy_file(&cached, &path.join("server.jar")).await;
+        }
+
+        // BuildTools fails late and with unclear errors on an unsupported Java
+        if let Some(requirement) = artifact.java {
+            let major = java_major_version(&options.java_path)?;
+            if !requirement.allows(major) {
+                return Err(Error::InvalidArgument(format!(
+                    "{} {} requires {}, but {} is Java {}",
+                    self.distribution,
+                    artifact.version,
+                    requirement,
+                    options.java_path.display(),
+                    major
+                )));
             }
-            sp.stop_and_persist("✔", "Successfully validated version".to_string());
-            version = ver;
-            break;
         }
 
-        Ok(Self { version })
+        let workspace = Workspace::new(cache).await?;
+        let result = self
+            .build(artifact, path, options, build_tools, &workspace, &jar_key)
+            .await;
+        workspace.cleanup(options.keep_build_cache).await?;
+
+        result
     }
+}
 
-    pub async fn install(&self, path: &Path, java_path: &Path) -> Result<()> {
-        // download buildtools
-        let bytes = Self::download_build_tools().await?;
-        let mut build_path = path.to_path_buf();
-        build_path.push("build_cache");
+impl Spigot {
+    /// `distribution` has to be Spigot or CraftBukkit.
+    pub fn new(http: Http, distribution: Distribution, base_url: String) -> Self {
+        Self {
+            http,
+            distribution,
+            base_url,
+        }
+    }
 
-        fs::create_dir_all(&build_path).await?;
+    /// BuildTools options of `options`, compiling CraftBukkit for the CraftBukkit distribution.
+    fn build_tools_options(&self, options: &InstallOptions) -> BuildToolsOptions {
+        let mut build_tools = options.build_tools.clone();
+        if self.distribution == Distribution::CraftBukkit {
+            build_tools.compile = Some(Compile::CraftBukkit);
+        }
+        build_tools
+    }
 
-        // buildtools.jar
-        let mut tool_path = build_path.clone();
-        tool_path.push("buildtools.jar");
-        let mut build_tools = File::create(&tool_path).await?;
-        build_tools.write_all(&bytes).await?;
+    /// Runs BuildTools in `workspace` and installs the built jar as `server.jar` into `path`.
+    async fn build(
+        &self,
+        artifact: &Artifact,
+        path: &Path,
+        options: &InstallOptions,
+        build_tools: &BuildToolsOptions,
+        workspace: &Workspace,
+        jar_key: &[String],
+    ) -> Result<()> {
+        let cache = options.verified_cache();
+
+        // download buildtools.jar
+        let tool_path = workspace.dir.join("BuildTools.jar");
+        match cache.zip(build_tools_key(&artifact.url)) {
+            Some((cache, key)) => {
+                let cached = cache
+                    .fetch(&self.http, &key, &artifact.url, None, "buildtools")
+                    .await?;
+                copy_file(&cached, &tool_path).await?;
+            }
+            None => {
+                let checksum = artifact.checksum.as_ref().filter(|_| !options.skip_verify);
+                self.http
+                    .download(&artifact.url, &tool_path, checksum, "buildtools")
+                    .await?;
+            }
+        }
 
         // run buildtools
-        let mut sp = Spinner::new(
-            Spinners::Dots,
-            "Building server.jar. This takes a few minutes...".to_string(),
-        );
-        let output = Command::new(java_path.as_os_str())
-            .current_dir(&build_path)
-            .arg("-jar")
-            .arg("buildtools.jar")
-            .arg("--rev")
-            .arg(&self.version)
-            .output()?;
-        if !output.status.success() {
-            let mut sp = Spinner::new(Spinners::Dots, "Deleting temp files...".to_string());
-            // SCARY
-            // delete build cache
-            fs::remove_dir_all(&build_path).await?;
-            sp.stop_and_persist("✔", "Finished deleting temp files".to_string());
-
-            let err = String::from_utf8(output.stderr)?;
+        fs::create_dir_all(path).await?;
+        let log = path.join("buildtools.log");
+        workspace
+            .run(&options.java_path, &artifact.build, build_tools, &log)
+            .await?;
+
+        let jar_name = build_tools.jar_name(&artifact.version);
+        let built = workspace.dir.join(&jar_name);
+        if !built.exists() {
             return Err(Error::Other(format!(
-                "Error while executing BuildTools: {}",
-                err
+                "BuildTools didn't build {}, see {}",
+                jar_name,
+                log.display()
             )));
         }
-        sp.stop_and_persist("✔", "Finished building server.jar".to_string());
-
-        let mut server_path = build_path.clone();
-        server_path.push(format!("spigot-{}.jar", self.version));
-        let server_path = Path::new(&server_path);
-        if !server_path.exists() {
-            return Err(Error::Other("Error while executing BuildTools".to_string()));
+        if let Some(cache) = cache {
+            cache.insert(jar_key, &built).await?;
         }
 
-        let mut new_path = path.to_path_buf();
-        new_path.push("server.jar");
-        fs::copy(server_path, new_path).await?;
-
-        let mut sp = Spinner::new(Spinners::Dots, "Deleting temp files...".to_string());
-        // SCARY
-        // delete build cache
-        fs::remove_dir_all(&build_path).await?;
-
-        sp.stop_and_persist("✔", "Finished deleting temp files".to_string());
+        copy_file(&built, &path.join("server.jar")).await?;
+        // the workspace is kept for the next build, its output isn't needed anymore
+        fs::remove_file(&built).await?;
 
         Ok(())
     }
+}
 
-    async fn check_version(version: &str) -> Result<bool> {
-        let url = format!("https://hub.spigotmc.org/versions/{}.json", version);
-        let client = reqwest::Client::new();
-        let res = client
-            .get(&url)
-            .header("User-Agent", "Fuck you spigot")
-            .send()
-            .await?;
-        let code = res.status();
-        Ok(code.is_success())
+impl Spigot {
+    async fn get_version_info(&self, version: &str) -> Result<String> {
+        let url = format!("{}/versions/{}.json", self.base_url, version);
+        self.http.get_text(&url).await
     }
 
-    async fn download_build_tools() -> Result<Bytes> {
-        let client = reqwest::Client::new();
-
-        let url = "https://hub.spigotmc.org/jenkins/job/BuildTools/api/json";
-        let res = client
-            .get(url)
-            .header("User-Agent", "automc client")
-            .send()
-            .await?
-            .error_for_status()?;
-        let body = res.text().await?;
+    async fn get_build_tools_url(&self) -> Result<String> {
+        let url = format!("{}/jenkins/job/BuildTools/api/json", self.base_url);
+        let body = self.http.get_text(&url).await?;
 
         let mut build_tools_url = gjson::get(&body, "builds.0.url").str().to_string();
         build_tools_url.push_str("artifact/target/BuildTools.jar");
 
-        let res = client
-            .get(build_tools_url)
-            .header("User-Agent", "automc client")
-            .send()
-            .await?
-            .error_for_status()?;
-
-        if let Some(file_size) = res.content_length() {
-            let pb = ProgressBar::new(file_size);
-            pb.set_style(ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-                .unwrap()
-                .progress_chars("=> "));
-
-            let mut content = Vec::with_capacity(file_size as usize);
-
-            let mut stream = res.bytes_stream();
-            while let Some(chunk) = stream.next().await {
-                let bytes = chunk?.to_vec();
-                pb.inc(bytes.len() as u64);
-                content.extend(bytes);
-            }
-            pb.finish();
-
-            Ok(Bytes::from(content))
-        } else {
-            let mut sp = Spinner::new(Spinners::Dots, "Downloading buildtools".to_string());
-            let content = res.bytes().await?;
-            sp.stop_and_persist("✔", "Finished downloading buildtools".to_string());
-
-            Ok(content)
-        }
+        Ok(build_tools_url)
     }
 }
@@ -1,56 +1,114 @@
-use crate::distribution::{download_file, install_server_jar};
+use crate::distribution::{
+    Artifact, Build, Channel, Checksum, Distribution, JavaRequirement, Provider, Version,
+};
 use crate::error::*;
-use inquire::{Confirm, Select};
+use crate::http::Http;
+use async_trait::async_trait;
 use serde::Deserialize;
-use spinners::{Spinner, Spinners};
-use std::fmt::{Display, Formatter};
-use std::path::Path;
 
+/// Hosts the version manifest links the version files and server jars on.
+const MOJANG_HOSTS: [&str; 4] = [
+    "https://piston-meta.mojang.com",
+    "https://piston-data.mojang.com",
+    "https://launchermeta.mojang.com",
+    "https://launcher.mojang.com",
+];
+
+/// Mojang's server, listed in the launcher's version manifest.
 pub struct Vanilla {
-    download_url: String,
+    http: Http,
+    base_url: String,
 }
 
 impl Vanilla {
-    pub async fn new() -> Result<Self> {
-        let mut sp = Spinner::new(Spinners::Dots, "Downloading metadata...".into());
-        let version_list = Self::get_versions().await?;
-        sp.stop_and_persist("✔", "Finished downloading metadata".into());
-
-        let options = {
-            let mut out = version_list.versions;
-            if Confirm::new("Only release versions?")
-                .with_default(true)
-                .prompt()?
-            {
-                out.retain(|v| v.channel == Channel::Release);
-            }
-            out
-        };
-        let version = Select::new("Select version", options).prompt()?;
-
-        let mut sp = Spinner::new(Spinners::Dots, "Downloading version info...".into());
-        let version_info = reqwest::get(version.url).await?.text().await?;
-        let url = gjson::get(&version_info, "downloads.server.url")
-            .str()
-            .to_owned();
-        sp.stop_and_persist("✔", "Finished downloading version info".into());
-
-        Ok(Self { download_url: url })
+    /// `base_url` is where the version manifest is fetched from, see [`ApiConfig::mojang`](crate::config::ApiConfig::mojang).
+    pub fn new(http: Http, base_url: String) -> Self {
+        Self { http, base_url }
+    }
+
+    async fn get_versions(&self) -> Result<VersionList> {
+        let url = format!("{}/mc/game/version_manifest.json", self.base_url);
+        self.http.get_json(&url).await
+    }
+
+    /// Points a URL on one of Mojang's hosts to the configured mirror, so version files and
+    /// server jars come from the mirror as well. The mirror has to serve Mojang's paths.
+    fn mirror_url(&self, url: &str) -> String {
+        if MOJANG_HOSTS.contains(&self.base_url.as_str()) {
+            return url.to_string();
+        }
+
+        MOJANG_HOSTS
+            .iter()
+            .find_map(|host| url.strip_prefix(host))
+            .filter(|path| path.starts_with('/'))
+            .map(|path| format!("{}{}", self.base_url, path))
+            .unwrap_or_else(|| url.to_string())
+    }
+}
+
+/// Mojang has no builds, so every version has a single build named after the version.
+#[async_trait]
+impl Provider for Vanilla {
+    fn http(&self) -> &Http {
+        &self.http
     }
 
-    pub async fn install(&self, path: &Path) -> Result<()> {
-        let bytes = download_file(&self.download_url, "server.jar").await?;
-        install_server_jar(path, &bytes).await?;
+    async fn versions(&self) -> Result<Vec<Version>> {
+        let version_list = self.get_versions().await?;
+
+        Ok(version_list
+            .versions
+            .into_iter()
+            .map(|v| Version {
+                id: v.version,
+                stable: v.channel == VersionType::Release,
+                support: None,
+            })
+            .collect())
+    }
 
-        Ok(())
+    async fn builds(&self, version: &str) -> Result<Vec<Build>> {
+        Ok(vec![Build {
+            id: version.to_string(),
+            channel: Channel::Default,
+            time: None,
+            changes: Vec::new(),
+            checksum: None,
+        }])
     }
 
-    async fn get_versions() -> Result<VersionList> {
-        let url = "https://launchermeta.mojang.com/mc/game/version_manifest.json";
-        let res = reqwest::get(url).await?.error_for_status()?;
-        let body = res.text().await?;
-        let ver = serde_json::from_str(&body)?;
-        Ok(ver)
+    async fn resolve(&self, version: &str, _build: &str) -> Result<Artifact> {
+        let version_list = self.get_versions().await?;
+        let info = version_list
+            .versions
+            .into_iter()
+            .find(|v| v.version == version)
+            .ok_or_else(|| Error::InvalidArgument(format!("unknown version {}", version)))?;
+
+        let version_info = self.http.get_text(&self.mirror_url(&info.url)).await?;
+        let server = gjson::get(&version_info, "downloads.server");
+        if !server.exists() {
+            return Err(Error::Other(format!(
+                "version {} has no server download",
+                version
+            )));
+        }
+        let java = gjson::get(&version_info, "javaVersion.majorVersion");
+
+        Ok(Artifact {
+            distribution: Distribution::Vanilla,
+            version: version.to_string(),
+            build: version.to_string(),
+            url: self.mirror_url(server.get("url").str()),
+            file_name: format!("minecraft_server.{}.jar", version),
+            checksum: Some(Checksum::Sha1(server.get("sha1").str().to_owned())),
+            java: java.exists().then(|| JavaRequirement {
+                min: java.u32(),
+                max: None,
+            }),
+            installer: None,
+        })
     }
 }
 
@@ -64,18 +122,12 @@ struct VersionInfo {
     #[serde(rename = "id")]
     version: String,
     #[serde(rename = "type")]
-    channel: Channel,
+    channel: VersionType,
     url: String,
 }
 
-impl Display for VersionInfo {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.version)
-    }
-}
-
 #[derive(Deserialize, Eq, PartialEq, Copy, Clone)]
-enum Channel {
+enum VersionType {
     #[serde(rename = "release")]
     Release,
     #[serde(rename = "snapshot", alias = "old_beta", alias = "old_alpha")]
@@ -1,17 +1,61 @@
+//! Error type shared by the whole crate.
+
 use std::io;
-use strum::Display;
 use thiserror::Error;
 
+/// Result with the crate's [`Error`](enum@Error).
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Error, Display, Debug)]
+/// Everything that can go wrong in automc.
+#[derive(Error, Debug)]
 pub enum Error {
+    /// A request failed.
+    #[error(transparent)]
     Api(#[from] reqwest::Error),
+    /// Reading or writing a file failed.
+    #[error(transparent)]
     Io(#[from] io::Error),
+    /// A prompt failed, e.g. because it was cancelled.
+    #[error(transparent)]
     Inquire(#[from] inquire::InquireError),
+    /// An API response or file isn't the expected JSON.
+    #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
+    /// Text that should be UTF-8 isn't.
+    #[error(transparent)]
     Encoding(#[from] std::string::FromUtf8Error),
+    /// The config file can't be read or written.
+    #[error(transparent)]
     Config(#[from] confy::ConfyError),
+    /// A TOML file, e.g. `automc.lock`, can't be parsed.
+    #[error(transparent)]
+    TomlDe(#[from] toml::de::Error),
+    /// A TOML file can't be written.
+    #[error(transparent)]
+    TomlSer(#[from] toml::ser::Error),
+    /// Java installations can't be located.
+    #[error(transparent)]
     JavaLocator(#[from] java_locator::errors::JavaLocatorError),
+    /// A value that can't be asked for without a terminal wasn't passed, named by its flag.
+    #[error("{0} is required when stdin is not a terminal")]
+    MissingArgument(&'static str),
+    /// A passed value is invalid.
+    #[error("{0}")]
+    InvalidArgument(String),
+    /// A download doesn't match its published checksum.
+    #[error("checksum mismatch for {file}: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        /// The downloaded file.
+        file: String,
+        /// Published checksum.
+        expected: String,
+        /// Checksum of the download.
+        actual: String,
+    },
+    /// `--offline` was given and the URL isn't cached.
+    #[error("{0} isn't cached, can't download it with --offline")]
+    Offline(String),
+    /// Any other error, described by its message.
+    #[error("{0}")]
     Other(String),
 }
@@ -0,0 +1,330 @@
+//! HTTP client with metadata caching, retries and resumable, verified downloads.
+
+use crate::cache::{part_path, unix_time, CachedResponse, MetadataCache};
+use crate::config::{DownloadConfig, HttpConfig};
+use crate::distribution::{Checksum, Hasher};
+use crate::error::*;
+use futures_util::StreamExt;
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::header::{
+    CONTENT_RANGE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RANGE,
+};
+use reqwest::{Certificate, NoProxy, Proxy, StatusCode};
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs::File;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+const USER_AGENT: &str = concat!("automc/", env!("CARGO_PKG_VERSION"));
+
+/// HTTP client shared by all distributions, configured from [`HttpConfig`].
+///
+/// Cloning is cheap and shares the underlying connection pool.
+#[derive(Clone, Debug)]
+pub struct Http {
+    client: reqwest::Client,
+    download: DownloadConfig,
+    metadata: Option<MetadataCache>,
+    offline: bool,
+}
+
+impl Http {
+    /// Client configured from `config`, retrying downloads as configured in `download`.
+    pub fn new(config: &HttpConfig, download: &DownloadConfig) -> Result<Self> {
+        let mut builder = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+            .read_timeout(Duration::from_secs(config.read_timeout_secs));
+
+        if let Some(proxy) = &config.proxy {
+            let proxy = Proxy::all(proxy)?
+                .no_proxy(config.no_proxy.as_deref().and_then(NoProxy::from_string));
+            builder = builder.proxy(proxy);
+        }
+
+        for path in &config.ca_certificates {
+            let pem = fs::read(path)?;
+            for cert in Certificate::from_pem_bundle(&pem)? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        Ok(Self {
+            client: builder.build()?,
+            download: download.clone(),
+            metadata: None,
+            offline: false,
+        })
+    }
+
+    /// Caches responses of [`Http::get_text`] and [`Http::get_json`] in `cache`.
+    pub fn with_metadata_cache(mut self, cache: MetadataCache) -> Self {
+        self.metadata = Some(cache);
+        self
+    }
+
+    /// Never touches the network: metadata is only read from the metadata cache and
+    /// downloads fail, so installs have to be served from the artifact cache.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Whether the client never touches the network, see [`Http::with_offline`].
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// The underlying client, for requests that need no caching or retries.
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Fetches `url`, going through the metadata cache if there is one.
+    pub async fn get_text(&self, url: &str) -> Result<String> {
+        let Some(cache) = &self.metadata else {
+            if self.offline {
+                return Err(Error::Offline(url.to_string()));
+            }
+            let res = self.client.get(url).send().await?.error_for_status()?;
+            return Ok(res.text().await?);
+        };
+
+        let cached = cache.load(url);
+        match &cached {
+            Some(cached) if self.offline || cache.is_fresh(cached) => {
+                return Ok(cached.body.clone())
+            }
+            None if self.offline => return Err(Error::Offline(url.to_string())),
+            _ => {}
+        }
+
+        let mut req = self.client.get(url);
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                req = req.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                req = req.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let res = req.send().await?.error_for_status()?;
+
+        let res = match cached {
+            Some(mut cached) if res.status() == StatusCode::NOT_MODIFIED => {
+                cached.fetched_at = unix_time();
+                cached
+            }
+            _ => {
+                let header = |name| {
+                    res.headers()
+                        .get(name)
+                        .and_then(|v| v.to_str().ok())
+                        .map(str::to_string)
+                };
+                CachedResponse {
+                    url: url.to_string(),
+                    etag: header(ETAG),
+                    last_modified: header(LAST_MODIFIED),
+                    fetched_at: unix_time(),
+                    body: res.text().await?,
+                }
+            }
+        };
+        // a cache that can't be written only costs a request next time
+        let _ = cache.store(&res);
+
+        Ok(res.body)
+    }
+
+    /// Fetches `url` like [`Http::get_text`] and parses it as JSON.
+    pub async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let body = self.get_text(url).await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Streams `url` into `dest` while showing a progress bar and verifies it against `checksum`.
+    ///
+    /// The download goes to a temporary file next to `dest`, which is only renamed to `dest`
+    /// once it is complete and verified. On failure the temporary file is deleted.
+    ///
+    /// Transient errors are retried with exponential backoff as configured in [`DownloadConfig`].
+    /// Retries resume the download with a range request if the server supports it.
+    pub async fn download(
+        &self,
+        url: &str,
+        dest: &Path,
+        checksum: Option<&Checksum>,
+        message: &str,
+    ) -> Result<()> {
+        let part = self.download_part(url, dest, checksum, message).await?;
+        tokio::fs::rename(&part, dest).await?;
+
+        Ok(())
+    }
+
+    /// Like [`Http::download`], but leaves the verified download in its temporary file next to
+    /// `dest` and returns its path.
+    pub(crate) async fn download_part(
+        &self,
+        url: &str,
+        dest: &Path,
+        checksum: Option<&Checksum>,
+        message: &str,
+    ) -> Result<PathBuf> {
+        if self.offline {
+            return Err(Error::Offline(url.to_string()));
+        }
+
+        let part = part_path(dest);
+        if let Err(e) = self.download_to(url, &part, checksum, message).await {
+            let _ = tokio::fs::remove_file(&part).await;
+            return Err(e);
+        }
+
+        Ok(part)
+    }
+
+    async fn download_to(
+        &self,
+        url: &str,
+        path: &Path,
+        checksum: Option<&Checksum>,
+        message: &str,
+    ) -> Result<()> {
+        let config = &self.download;
+
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(
+            ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] {msg} {bytes}")
+                .unwrap(),
+        );
+        pb.set_message(format!("Downloading {}", message));
+
+        let mut download = PartialDownload {
+            file: File::create(path).await?,
+            checksum,
+            hasher: checksum.map(Checksum::hasher),
+            written: 0,
+            pb,
+        };
+
+        let mut attempt = 0;
+        loop {
+            match download.fetch(&self.client, url).await {
+                Ok(()) => break,
+                Err(Error::Api(e)) if attempt < config.retries && is_transient(&e) => {
+                    let delay = config.backoff_ms.saturating_mul(1 << attempt.min(16));
+                    attempt += 1;
+                    download.pb.println(format!(
+                        "Download failed ({}), retrying in {} ms ({}/{})",
+                        e, delay, attempt, config.retries
+                    ));
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                }
+                Err(e) => {
+                    download.pb.abandon();
+                    return Err(e);
+                }
+            }
+        }
+        download.pb.finish();
+        download.file.sync_all().await?;
+
+        if let (Some(checksum), Some(hasher)) = (checksum, download.hasher) {
+            let actual = hasher.finalize();
+            if !actual.eq_ignore_ascii_case(checksum.value()) {
+                return Err(Error::ChecksumMismatch {
+                    file: message.to_string(),
+                    expected: checksum.to_string(),
+                    actual,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Connection problems, timeouts, server errors and rate limiting are worth retrying.
+fn is_transient(e: &reqwest::Error) -> bool {
+    match e.status() {
+        Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+        // a dropped connection while streaming the body surfaces as a decode error
+        None => e.is_timeout() || e.is_connect() || e.is_request() || e.is_body() || e.is_decode(),
+    }
+}
+
+/// First byte of a partial response, e.g. 100 of `Content-Range: bytes 100-999/1000`.
+fn range_start(res: &reqwest::Response) -> Option<u64> {
+    res.headers()
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// State of a download that may be resumed after a failed attempt.
+struct PartialDownload<'a> {
+    file: File,
+    checksum: Option<&'a Checksum>,
+    hasher: Option<Hasher>,
+    written: u64,
+    pb: ProgressBar,
+}
+
+impl PartialDownload<'_> {
+    async fn fetch(&mut self, client: &reqwest::Client, url: &str) -> Result<()> {
+        let mut req = client.get(url);
+        if self.written > 0 {
+            req = req.header(RANGE, format!("bytes={}-", self.written));
+        }
+        let mut res = req.send().await?.error_for_status()?;
+
+        let partial = res.status() == StatusCode::PARTIAL_CONTENT;
+        if self.written > 0 && !(partial && range_start(&res) == Some(self.written)) {
+            // the server ignored the range or sent a different one, start over
+            self.restart().await?;
+            if partial {
+                res = client.get(url).send().await?.error_for_status()?;
+            }
+        }
+
+        if let Some(remaining) = res.content_length() {
+            self.pb.set_length(self.written + remaining);
+            self.pb.set_style(ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
+                .unwrap()
+                .progress_chars("=> "));
+        }
+
+        let mut stream = res.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let bytes = chunk?;
+            self.file.write_all(&bytes).await?;
+            if let Some(hasher) = &mut self.hasher {
+                hasher.update(&bytes);
+            }
+            self.written += bytes.len() as u64;
+            self.pb.inc(bytes.len() as u64);
+        }
+
+        Ok(())
+    }
+
+    async fn restart(&mut self) -> Result<()> {
+        self.file.set_len(0).await?;
+        self.file.rewind().await?;
+        self.hasher = self.checksum.map(Checksum::hasher);
+        self.written = 0;
+        self.pb.set_position(0);
+
+        Ok(())
+    }
+}
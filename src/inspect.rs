@@ -0,0 +1,414 @@
+//! Identifies installed servers from their lockfile or server jar.
+
+use crate::distribution::{Artifact, BuildToolsOptions, Checksum, Distribution, Provider};
+use crate::error::*;
+use crate::lock::{Lockfile, LOCKFILE};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+/// What could be determined about a server jar by looking at its contents.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JarInfo {
+    /// Distribution the jar belongs to.
+    pub distribution: Option<Distribution>,
+    /// Minecraft (or Velocity) version.
+    pub version: Option<String>,
+    /// Build id, if the jar records it.
+    pub build: Option<String>,
+}
+
+/// What is installed in a server directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Installed {
+    /// Installed distribution.
+    pub distribution: Distribution,
+    /// Installed version.
+    pub version: String,
+    /// Unknown for some jars not installed by automc.
+    pub build: Option<String>,
+    /// Java recorded in the lockfile.
+    pub java_path: Option<PathBuf>,
+    /// Fabric installer recorded in the lockfile.
+    pub installer: Option<String>,
+    /// BuildTools options recorded in the lockfile.
+    pub build_tools: Option<BuildToolsOptions>,
+}
+
+/// Reads what is installed in `dir` from its [`Lockfile`], or for servers not installed by
+/// automc, from the server jar.
+pub fn inspect_dir(dir: &Path) -> Result<Installed> {
+    if let Some(lock) = Lockfile::load(dir)? {
+        return Ok(Installed {
+            distribution: lock.distribution,
+            version: lock.version,
+            build: Some(lock.build),
+            java_path: Some(lock.java_path),
+            installer: lock.installer,
+            build_tools: lock.build_tools,
+        });
+    }
+
+    let jar = dir.join("server.jar");
+    if !jar.exists() {
+        return Err(Error::InvalidArgument(format!(
+            "no server.jar or {} in {}",
+            LOCKFILE,
+            dir.display()
+        )));
+    }
+
+    let info = inspect_jar(&jar)?;
+    match (info.distribution, info.version) {
+        (Some(distribution), Some(version)) => Ok(Installed {
+            distribution,
+            version,
+            build: info.build,
+            java_path: None,
+            installer: None,
+            build_tools: None,
+        }),
+        _ => Err(Error::Other(format!("can't identify {}", jar.display()))),
+    }
+}
+
+/// Finds the build of `version` whose published checksum matches `jar`, checking at most
+/// `limit` of the newest builds. Always `None` for distributions without checksums.
+///
+/// Checksums are taken from the build list where the API includes them, so only the matching
+/// build is resolved.
+pub async fn find_build(
+    provider: &dyn Provider,
+    version: &str,
+    jar: &Path,
+    limit: usize,
+) -> Result<Option<Artifact>> {
+    let digests = Digests::of(jar)?;
+
+    for build in provider.builds(version).await?.iter().take(limit) {
+        if let Some(checksum) = &build.checksum {
+            if digests.matches(checksum) {
+                return Ok(Some(provider.resolve(version, &build.id).await?));
+            }
+            continue;
+        }
+
+        let artifact = provider.resolve(version, &build.id).await?;
+        match &artifact.checksum {
+            Some(checksum) if digests.matches(checksum) => return Ok(Some(artifact)),
+            Some(_) => {}
+            None => return Ok(None),
+        }
+    }
+
+    Ok(None)
+}
+
+/// Digests of a file in every algorithm a [`Checksum`] can use, computed in one pass.
+struct Digests {
+    sha256: String,
+    sha1: String,
+    md5: String,
+}
+
+impl Digests {
+    fn of(path: &Path) -> Result<Self> {
+        let mut sha256 = Sha256::new();
+        let mut sha1 = Sha1::new();
+        let mut md5 = Md5::new();
+
+        let mut file = File::open(path)?;
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let n = file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            sha256.update(&buf[..n]);
+            sha1.update(&buf[..n]);
+            md5.update(&buf[..n]);
+        }
+
+        Ok(Self {
+            sha256: hex::encode(sha256.finalize()),
+            sha1: hex::encode(sha1.finalize()),
+            md5: hex::encode(md5.finalize()),
+        })
+    }
+
+    fn matches(&self, checksum: &Checksum) -> bool {
+        let digest = match checksum {
+            Checksum::Sha256(_) => &self.sha256,
+            Checksum::Sha1(_) => &self.sha1,
+            Checksum::Md5(_) => &self.md5,
+        };
+        digest.eq_ignore_ascii_case(checksum.value())
+    }
+}
+
+/// Identifies the distribution, version and, where the jar records it, the build of a server jar.
+pub fn inspect_jar(path: &Path) -> Result<JarInfo> {
+    let mut jar = ZipArchive::new(File::open(path)?).map_err(io::Error::from)?;
+
+    let manifest = read_entry(&mut jar, "META-INF/MANIFEST.MF")?
+        .map(|m| parse_manifest(&m))
+        .unwrap_or_default();
+    let main_class = manifest.get("Main-Class").map(String::as_str).unwrap_or("");
+
+    // Fabric's server launcher
+    if let Some(properties) = read_entry(&mut jar, "install.properties")? {
+        let properties = parse_properties(&properties);
+        return Ok(JarInfo {
+            distribution: Some(Distribution::Fabric),
+            version: properties.get("game-version").cloned(),
+            build: properties.get("fabric-loader-version").cloned(),
+        });
+    }
+
+    if main_class.starts_with("com.velocitypowered.") {
+        // e.g. `3.3.0-SNAPSHOT (git-8abc2b35-b436)`
+        let implementation = manifest.get("Implementation-Version");
+        let (version, build) = match implementation.and_then(|v| v.split_once(" (")) {
+            Some((version, git)) => (
+                Some(version.to_string()),
+                git.trim_end_matches(')')
+                    .rsplit_once("-b")
+                    .map(|(_, build)| build.to_string()),
+            ),
+            None => (implementation.cloned(), None),
+        };
+        return Ok(JarInfo {
+            distribution: Some(Distribution::Velocity),
+            version,
+            build,
+        });
+    }
+
+    let version = read_entry(&mut jar, "version.json")?
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+        .and_then(|json| json["id"].as_str().map(str::to_string));
+
+    // Paperclip, used by Paper, Folia and Purpur, and the bootstrap jar of Spigot and CraftBukkit
+    // name the bundled jar after the project, e.g. `1.21.1/paper-1.21.1.jar` or
+    // `spigot-1.21.1-R0.1-SNAPSHOT.jar`
+    if let Some(versions) = read_entry(&mut jar, "META-INF/versions.list")? {
+        let bundled = versions
+            .lines()
+            .filter_map(|line| line.split('\t').nth(2))
+            .filter_map(|path| path.rsplit('/').next())
+            .find_map(|name| {
+                let (project, rest) = name.split_once('-')?;
+                let distribution = match project {
+                    "paper" => Distribution::Paper,
+                    "folia" => Distribution::Folia,
+                    "purpur" => Distribution::Purpur,
+                    "spigot" => Distribution::Spigot,
+                    "craftbukkit" => Distribution::CraftBukkit,
+                    _ => return None,
+                };
+                Some((distribution, rest.trim_end_matches(".jar").to_string()))
+            });
+        // Vanilla's bundler lists its jar as well, e.g. `1.21.1/server-1.21.1.jar`
+        if let Some((distribution, bundled_version)) = bundled {
+            return Ok(JarInfo {
+                distribution: Some(distribution),
+                version: version.or_else(|| Some(spigot_version(&bundled_version).to_string())),
+                build: None,
+            });
+        }
+    }
+
+    // legacy Paperclip
+    if let Some(properties) = read_entry(&mut jar, "patch.properties")? {
+        let properties = parse_properties(&properties);
+        return Ok(JarInfo {
+            distribution: Some(Distribution::Paper),
+            version: version.or_else(|| properties.get("version").cloned()),
+            build: None,
+        });
+    }
+
+    // Spigot and CraftBukkit before 1.18, plain shaded jars
+    for (pom, distribution) in [
+        (
+            "META-INF/maven/org.spigotmc/spigot/pom.properties",
+            Distribution::Spigot,
+        ),
+        (
+            "META-INF/maven/org.bukkit/craftbukkit/pom.properties",
+            Distribution::CraftBukkit,
+        ),
+    ] {
+        if let Some(pom) = read_entry(&mut jar, pom)? {
+            let properties = parse_properties(&pom);
+            return Ok(JarInfo {
+                distribution: Some(distribution),
+                version: version.or_else(|| {
+                    properties
+                        .get("version")
+                        .map(|v| spigot_version(v).to_string())
+                }),
+                build: None,
+            });
+        }
+    }
+
+    if main_class == "net.minecraft.bundler.Main" || main_class.starts_with("net.minecraft.server.")
+    {
+        return Ok(JarInfo {
+            distribution: Some(Distribution::Vanilla),
+            // vanilla has a single build per version
+            build: version.clone(),
+            version,
+        });
+    }
+
+    Ok(JarInfo {
+        version,
+        ..Default::default()
+    })
+}
+
+/// Minecraft version of a Spigot or CraftBukkit artifact version, e.g. `1.21.1` of `1.21.1-R0.1-SNAPSHOT`.
+fn spigot_version(version: &str) -> &str {
+    version.split("-R").next().unwrap_or(version)
+}
+
+fn read_entry(jar: &mut ZipArchive<File>, name: &str) -> Result<Option<String>> {
+    let mut entry = match jar.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(io::Error::from(e).into()),
+    };
+
+    let mut content = String::new();
+    entry.read_to_string(&mut content)?;
+    Ok(Some(content))
+}
+
+/// Parses a jar manifest, joining continuation lines.
+fn parse_manifest(content: &str) -> HashMap<String, String> {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for line in content.lines() {
+        match line.strip_prefix(' ') {
+            Some(continuation) => {
+                if let Some((_, value)) = entries.last_mut() {
+                    value.push_str(continuation);
+                }
+            }
+            None => {
+                if let Some((key, value)) = line.split_once(": ") {
+                    entries.push((key.to_string(), value.to_string()));
+                }
+            }
+        }
+    }
+    entries.into_iter().collect()
+}
+
+fn parse_properties(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    /// Writes a jar containing `entries` to a temporary file.
+    fn jar(name: &str, entries: &[(&str, &str)]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "automc-inspect-{}-{}.jar",
+            std::process::id(),
+            name
+        ));
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        for (entry, content) in entries {
+            zip.start_file(*entry, SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn vanilla_bundler() {
+        let path = jar(
+            "vanilla",
+            &[
+                (
+                    "META-INF/MANIFEST.MF",
+                    "Manifest-Version: 1.0\nMain-Class: net.minecraft.bundler.Main\n",
+                ),
+                (
+                    "META-INF/versions.list",
+                    "abc\t1.21.1\t1.21.1/server-1.21.1.jar\n",
+                ),
+                ("version.json", r#"{"id": "1.21.1"}"#),
+            ],
+        );
+        let info = inspect_jar(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(
+            info,
+            JarInfo {
+                distribution: Some(Distribution::Vanilla),
+                version: Some("1.21.1".to_string()),
+                build: Some("1.21.1".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn paperclip() {
+        let path = jar(
+            "paper",
+            &[
+                (
+                    "META-INF/MANIFEST.MF",
+                    "Manifest-Version: 1.0\nMain-Class: io.papermc.paperclip.Main\n",
+                ),
+                (
+                    "META-INF/versions.list",
+                    "abc\tpaper:1.21.1\t1.21.1/paper-1.21.1.jar\n",
+                ),
+                ("version.json", r#"{"id": "1.21.1"}"#),
+            ],
+        );
+        let info = inspect_jar(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(info.distribution, Some(Distribution::Paper));
+        assert_eq!(info.version.as_deref(), Some("1.21.1"));
+    }
+
+    #[test]
+    fn craftbukkit_bootstrap() {
+        let path = jar(
+            "craftbukkit",
+            &[(
+                "META-INF/versions.list",
+                "abc\torg.bukkit:craftbukkit:1.21.1-R0.1-SNAPSHOT\tcraftbukkit-1.21.1-R0.1-SNAPSHOT.jar\n",
+            )],
+        );
+        let info = inspect_jar(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(info.distribution, Some(Distribution::CraftBukkit));
+        assert_eq!(info.version.as_deref(), Some("1.21.1"));
+    }
+}
@@ -1,9 +1,12 @@
+//! Finding Java installations and their versions.
+
 use crate::error::*;
 use java_locator::locate_java_home;
 use std::io::BufRead;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Paths of the Java binaries on `PATH`, falling back to `JAVA_HOME`.
 #[cfg(windows)]
 pub fn java_versions() -> Result<Vec<PathBuf>> {
     let output = Command::new("where").arg("java").output()?;
@@ -29,6 +32,7 @@ pub fn java_versions() -> Result<Vec<PathBuf>> {
     Ok(java_versions)
 }
 
+/// Paths of the Java binaries on `PATH`, falling back to `JAVA_HOME`.
 #[cfg(unix)]
 pub fn java_versions() -> Result<Vec<PathBuf>> {
     let output = Command::new("which").arg("java").output()?;
@@ -53,3 +57,33 @@ pub fn java_versions() -> Result<Vec<PathBuf>> {
 
     Ok(java_versions)
 }
+
+/// Major version of the Java at `path`, e.g. 21 for `21.0.2` or 8 for `1.8.0_392`.
+pub fn java_major_version(path: &Path) -> Result<u32> {
+    let output = Command::new(path).arg("-version").output()?;
+    if !output.status.success() {
+        return Err(Error::Other(format!("{} -version failed", path.display())));
+    }
+
+    // `java -version` prints e.g. `openjdk version "21.0.2" 2024-01-16` to stderr, possibly
+    // after `Picked up JAVA_TOOL_OPTIONS: …` lines
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    stderr
+        .lines()
+        .find(|line| line.contains("version \""))
+        .and_then(|line| line.split('"').nth(1))
+        .and_then(|version| {
+            let mut parts = version.split(['.', '_', '-', '+']);
+            match parts.next()? {
+                "1" => parts.next()?.parse().ok(),
+                major => major.parse().ok(),
+            }
+        })
+        .ok_or_else(|| {
+            Error::Other(format!(
+                "can't read the version of {} from `{}`",
+                path.display(),
+                stderr.trim()
+            ))
+        })
+}
@@ -0,0 +1,150 @@
+//! Library behind the `automc` CLI for installing Minecraft server software.
+//!
+//! ```no_run
+//! # async fn example() -> automc::error::Result<()> {
+//! use automc::config::{ApiConfig, DownloadConfig, HttpConfig};
+//! use automc::distribution::{Distribution, InstallOptions};
+//! use automc::http::Http;
+//! use std::path::Path;
+//!
+//! let http = Http::new(&HttpConfig::default(), &DownloadConfig::default())?;
+//! let provider = Distribution::Paper.provider(&http, &ApiConfig::default());
+//! let artifact = automc::resolve(provider.as_ref(), "1.21.1", "latest").await?;
+//! let options = InstallOptions {
+//!     java_path: "java".into(),
+//!     ..Default::default()
+//! };
+//! automc::install(provider.as_ref(), &artifact, Path::new("server"), &options).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+
+use crate::distribution::{
+    install_eula, install_start_script, Artifact, Build, Channel, Distribution, InstallOptions,
+    Provider, Version,
+};
+use crate::error::*;
+use crate::lock::Lockfile;
+use crate::version::VersionConstraint;
+use futures_util::future::join;
+use std::path::Path;
+use strum::IntoEnumIterator;
+
+pub mod cache;
+pub mod config;
+pub mod distribution;
+pub mod error;
+pub mod http;
+pub mod inspect;
+pub mod java;
+pub mod lock;
+pub mod registry;
+pub mod spec;
+pub mod version;
+
+/// All supported distributions, sorted by name.
+pub fn distributions() -> Vec<Distribution> {
+    let mut distributions: Vec<Distribution> = Distribution::iter().collect();
+    distributions.sort_by_key(|d| d.to_string());
+    distributions
+}
+
+/// Newest build of `channel`.
+pub fn latest_build(builds: &[Build], channel: Channel) -> Option<&Build> {
+    builds.iter().find(|b| b.channel == channel)
+}
+
+/// Builds after `from` up to and including `to`, newest first like `builds`. Without `from`,
+/// or if it isn't one of `builds`, every build up to `to` is included.
+pub fn builds_between<'a>(builds: &'a [Build], from: Option<&str>, to: &str) -> Vec<&'a Build> {
+    builds
+        .iter()
+        .skip_while(|b| b.id != to)
+        .take_while(|b| Some(b.id.as_str()) != from)
+        .collect()
+}
+
+/// Resolves a version constraint to a version id of `provider`.
+///
+/// See [`VersionConstraint`] for the accepted constraints.
+pub async fn resolve_version(provider: &dyn Provider, constraint: &str) -> Result<String> {
+    let versions = provider.versions().await?;
+    find_version(&versions, constraint)
+}
+
+/// Resolves a version constraint against `versions`, the version list of a provider.
+pub fn find_version(versions: &[Version], constraint: &str) -> Result<String> {
+    let parsed: VersionConstraint = constraint.parse()?;
+
+    parsed
+        .select(versions)
+        .map(|v| v.id.clone())
+        .ok_or_else(|| match parsed {
+            VersionConstraint::Exact(_) => {
+                Error::InvalidArgument(format!("unknown version {}", constraint))
+            }
+            _ => Error::InvalidArgument(format!("no version matches {}", constraint)),
+        })
+}
+
+/// Resolves `version` and `build` to a downloadable artifact.
+///
+/// `build` is either a build id or `latest` for the newest build of the default channel.
+pub async fn resolve(provider: &dyn Provider, version: &str, build: &str) -> Result<Artifact> {
+    let builds = provider.builds(version).await?;
+
+    let build = match build {
+        "latest" => latest_build(&builds, Channel::Default),
+        build => builds.iter().find(|b| b.id == build),
+    }
+    .ok_or_else(|| Error::InvalidArgument(format!("unknown build {}", build)))?;
+
+    provider.resolve(version, &build.id).await
+}
+
+/// Sets up a server in `dir`: writes the start script using the configured Java, accepts
+/// the EULA, installs the server jar and records it in the [`Lockfile`].
+///
+/// Callers are responsible for asking the user to accept the EULA beforehand.
+pub async fn install(
+    provider: &dyn Provider,
+    artifact: &Artifact,
+    dir: &Path,
+    options: &InstallOptions,
+) -> Result<()> {
+    let start_script = install_start_script(dir, &options.java_path, options.memory.as_deref());
+    let eula = install_eula(dir);
+
+    let res = join(start_script, eula).await;
+    res.0?;
+    res.1?;
+
+    provider.install(artifact, dir, options).await?;
+
+    Lockfile::new(artifact, &options.java_path)
+        .with_build_tools(&options.build_tools)
+        .save(dir)
+}
+
+/// Replaces the server jar in `dir` with `artifact`, keeping the start script, EULA and all
+/// other files. The previous jar is kept as `server.jar.bak` and the [`Lockfile`] is updated.
+pub async fn update(
+    provider: &dyn Provider,
+    artifact: &Artifact,
+    dir: &Path,
+    options: &InstallOptions,
+) -> Result<()> {
+    let server_jar = dir.join("server.jar");
+    if server_jar.exists() {
+        // copy instead of rename, the server stays intact if the install fails
+        tokio::fs::copy(&server_jar, dir.join("server.jar.bak")).await?;
+    }
+
+    provider.install(artifact, dir, options).await?;
+
+    Lockfile::new(artifact, &options.java_path)
+        .with_build_tools(&options.build_tools)
+        .save(dir)
+}
@@ -0,0 +1,103 @@
+//! The `automc.lock` recording what was installed into a server directory.
+
+use crate::distribution::{Artifact, BuildToolsOptions, Checksum, Distribution};
+use crate::error::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the lockfile in a server directory.
+pub const LOCKFILE: &str = "automc.lock";
+
+/// Records exactly what was installed into a server directory, so the install can be
+/// reproduced with `automc install --locked`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Lockfile {
+    /// Installed distribution.
+    pub distribution: Distribution,
+    /// Installed version.
+    pub version: String,
+    /// Build id, the loader version for Fabric and the BuildTools revision for Spigot and
+    /// CraftBukkit.
+    pub build: String,
+    /// Where the server jar (or BuildTools for Spigot and CraftBukkit) was downloaded from.
+    pub url: String,
+    /// File name the API gives the jar.
+    pub file_name: String,
+    /// Checksum published by the distribution's API.
+    pub checksum: Option<Checksum>,
+    /// Installer version the Fabric server launcher was built with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installer: Option<String>,
+    /// Java the start script runs.
+    pub java_path: PathBuf,
+    /// automc version that wrote the lockfile.
+    pub automc_version: String,
+    /// BuildTools options Spigot or CraftBukkit was built with, if any were set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_tools: Option<BuildToolsOptions>,
+}
+
+impl Lockfile {
+    /// Lockfile recording `artifact` installed with `java_path`.
+    pub fn new(artifact: &Artifact, java_path: &Path) -> Self {
+        Self {
+            distribution: artifact.distribution,
+            version: artifact.version.clone(),
+            build: artifact.build.clone(),
+            url: artifact.url.clone(),
+            file_name: artifact.file_name.clone(),
+            checksum: artifact.checksum.clone(),
+            installer: artifact.installer.clone(),
+            java_path: java_path.to_path_buf(),
+            automc_version: env!("CARGO_PKG_VERSION").to_string(),
+            build_tools: None,
+        }
+    }
+
+    /// Records `options` if the server is built with BuildTools and they change the build.
+    pub fn with_build_tools(mut self, options: &BuildToolsOptions) -> Self {
+        let built = matches!(
+            self.distribution,
+            Distribution::Spigot | Distribution::CraftBukkit
+        );
+        self.build_tools = Some(options.clone()).filter(|o| built && o.is_set());
+        self
+    }
+
+    /// Reads the lockfile of the server in `dir`, if it has one.
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(LOCKFILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path)?;
+        Ok(Some(toml::from_str(&content)?))
+    }
+
+    /// Writes the lockfile into `dir`.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let content = format!(
+            "# Generated by automc, reproduce with `automc install --locked`\n{}",
+            toml::to_string(self)?
+        );
+        fs::write(dir.join(LOCKFILE), content)?;
+
+        Ok(())
+    }
+
+    /// The locked artifact, installable without asking the distribution's API again.
+    pub fn artifact(&self) -> Artifact {
+        Artifact {
+            distribution: self.distribution,
+            version: self.version.clone(),
+            build: self.build.clone(),
+            url: self.url.clone(),
+            file_name: self.file_name.clone(),
+            checksum: self.checksum.clone(),
+            java: None,
+            installer: self.installer.clone(),
+        }
+    }
+}
@@ -1,89 +1,61 @@
-use crate::args::Args;
-use crate::config::Config;
-use crate::distribution::*;
-use crate::java::java_versions;
+use crate::args::{Args, Command};
+use automc::cache::Cache;
+use automc::config::Config;
+use automc::error::*;
+use automc::http::Http;
 use clap::Parser;
-use error::*;
-use futures_util::future::join;
-use inquire::{Confirm, Select, Text};
-use std::path::PathBuf;
-use strum::IntoEnumIterator;
+use std::process::ExitCode;
+use std::time::Duration;
 
 mod args;
-mod config;
-mod distribution;
-mod error;
-mod java;
+mod commands;
+mod prompt;
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let args = Args::parse();
-    let mut config = Config::load()?;
-
-    let dir: PathBuf = Text::new("Select directory")
-        .with_help_message("leave empty for current directory")
-        .prompt()?
-        .into();
-
-    let mut options: Vec<Distribution> = Distribution::iter().collect();
-    options.sort_by_key(|a| a.to_string());
-
-    let distribution = Select::new("Select distribution", options).prompt()?;
-
-    if !config.accepted_eula {
-        if !Confirm::new("Do you accept the EULA?")
-            .with_help_message("https://www.minecraft.net/eula")
-            .with_default(true)
-            .prompt()?
-        {
-            return Ok(());
-        } else {
-            config.accepted_eula = true;
-            config.save()?;
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
         }
     }
+}
 
-    let java_path = match args.java_path {
-        Some(path) => PathBuf::from(path),
-        None => {
-            let mut java_versions = java_versions()?
-                .iter()
-                .map(|path| path.display().to_string())
-                .collect::<Vec<String>>();
-            java_versions.push("Custom".to_string());
-            java_versions.push("use JAVA_HOME".to_string());
-
-            let selected = Select::new("Select java version", java_versions).prompt()?;
-
-            let path = match selected.as_str() {
-                "Custom" => Text::new("Path to custom java binary")
-                    .with_help_message("eg. /usr/lib/jvm/bin/java")
-                    .prompt()?,
-                "use JAVA_HOME" => "java".to_string(),
-                _ => selected,
-            };
-
-            PathBuf::from(path)
-        }
-    };
-
-    let start_script = install_start_script(&dir, &java_path);
-    let eula = install_eula(&dir);
-
-    let res = join(start_script, eula).await;
-    res.0?;
-    res.1?;
-
-    match distribution {
-        Distribution::Paper => Paper::new().await?.install(&dir).await?,
-        Distribution::Folia => Folia::new().await?.install(&dir).await?,
-        Distribution::Velocity => Velocity::new().await?.install(&dir).await?,
-        Distribution::Purpur => Purpur::new().await?.install(&dir).await?,
-        Distribution::Fabric => Fabric::new().await?.install(&dir).await?,
-        Distribution::Vanilla => Vanilla::new().await?.install(&dir).await?,
-        Distribution::Spigot => Spigot::new().await?.install(&dir, &java_path).await?,
-    };
+async fn run() -> Result<()> {
+    let args = Args::parse();
+    let mut config = Config::load()?;
+    // writes settings added since the config file was created with their defaults
+    config.save()?;
 
-    Text::new("Press <ENTER> to exit...").prompt()?;
-    Ok(())
+    // flags only apply to this run and are not saved to the config
+    let mut http_config = config.http.clone();
+    if let Some(proxy) = args.proxy {
+        http_config.proxy = Some(proxy);
+    }
+    http_config.ca_certificates.extend(args.ca_certs);
+    let cache = Cache::new(Cache::default_dir()?);
+    let metadata = cache.metadata(Duration::from_secs(config.cache.metadata_ttl_secs));
+    let http = Http::new(&http_config, &config.download)?
+        .with_metadata_cache(metadata)
+        .with_offline(args.offline);
+    let mut api = config.api.clone();
+    api.apply_env();
+
+    match args.command.unwrap_or(Command::Install(Default::default())) {
+        Command::Install(args) => commands::install::execute(args, &mut config, &api, &http).await,
+        Command::Update(args) => commands::update::execute(args, &api, &http).await,
+        Command::List => commands::list::execute().await,
+        Command::Info(args) => commands::info::execute(args).await,
+        Command::Outdated(args) => commands::outdated::execute(args, &api, &http).await,
+        Command::Builds(args) => commands::builds::execute(args, &api, &http).await,
+        Command::Changelog(args) => commands::changelog::execute(args, &api, &http).await,
+        Command::Run(args) => commands::run::execute(args).await,
+        Command::Backup(args) => commands::backup::execute(args).await,
+        Command::Doctor => commands::doctor::execute(&config, &api, &http).await,
+        Command::Cache(args) => commands::cache::execute(args).await,
+        Command::Apply(args) => commands::apply::execute(args, &api, &http).await,
+        Command::Remove(args) => commands::remove::execute(args).await,
+        Command::Adopt(args) => commands::adopt::execute(args, &api, &http).await,
+    }
 }
@@ -0,0 +1,33 @@
+use automc::error::*;
+use inquire::Confirm;
+use std::io::{stdin, IsTerminal};
+
+pub fn is_interactive() -> bool {
+    stdin().is_terminal()
+}
+
+/// Returns `value` if it was passed on the command line, otherwise asks using `prompt`.
+/// Without a terminal there is nobody to ask, so a missing value is an error naming `flag`.
+pub fn value_or_prompt<T>(
+    value: Option<T>,
+    flag: &'static str,
+    prompt: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    match value {
+        Some(value) => Ok(value),
+        None if is_interactive() => prompt(),
+        None => Err(Error::MissingArgument(flag)),
+    }
+}
+
+/// Asks whether to hide unstable entries, unless `--unstable` was passed.
+/// Defaults to stable only when not running in a terminal.
+pub fn only_stable(unstable: bool, message: &str) -> Result<bool> {
+    if unstable {
+        Ok(false)
+    } else if is_interactive() {
+        Ok(Confirm::new(message).with_default(true).prompt()?)
+    } else {
+        Ok(true)
+    }
+}
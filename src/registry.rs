@@ -0,0 +1,141 @@
+//! Named server instances, stored in `instances.toml` next to the config.
+
+use crate::config::APP_NAME;
+use crate::distribution::Distribution;
+use crate::error::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const REGISTRY_NAME: &str = "instances";
+
+/// Named server instances managed by automc, stored in `instances.toml` next to the config.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Registry {
+    /// Instances by name.
+    #[serde(default)]
+    pub instances: BTreeMap<String, Instance>,
+}
+
+/// A registered server.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Instance {
+    /// Absolute path of the server directory.
+    pub path: PathBuf,
+    /// Distribution installed in the directory.
+    pub distribution: Distribution,
+    /// `server-port` from `server.properties`, unknown before the server first started.
+    pub port: Option<u16>,
+    /// Java used by the server, if known.
+    pub java_path: Option<PathBuf>,
+}
+
+impl Instance {
+    /// Describes the server in `path`, reading its port from `server.properties`.
+    pub fn new(
+        path: &Path,
+        distribution: Distribution,
+        java_path: Option<PathBuf>,
+    ) -> Result<Self> {
+        Ok(Self {
+            path: path.canonicalize()?,
+            distribution,
+            port: server_port(path),
+            java_path,
+        })
+    }
+}
+
+impl Registry {
+    /// Reads the registry, which is empty if there is none yet.
+    pub fn load() -> Result<Self> {
+        Ok(confy::load(APP_NAME, Some(REGISTRY_NAME))?)
+    }
+
+    /// Writes the registry.
+    pub fn save(&self) -> Result<()> {
+        Ok(confy::store(APP_NAME, Some(REGISTRY_NAME), self)?)
+    }
+
+    /// Instance registered as `name`.
+    pub fn get(&self, name: &str) -> Option<&Instance> {
+        self.instances.get(name)
+    }
+
+    /// Name of the instance in `path`, if it is registered.
+    pub fn find_by_path(&self, path: &Path) -> Option<&str> {
+        let path = path.canonicalize().ok()?;
+        self.instances
+            .iter()
+            .find(|(_, instance)| instance.path == path)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Registers `instance` as `name`, or if no name is given, updates the entry of the same
+    /// directory or registers it under the directory name. Returns the used name.
+    pub fn register(&mut self, name: Option<&str>, instance: Instance) -> Result<String> {
+        let name = match name {
+            Some(name) => {
+                self.check_name(name, &instance.path)?;
+                name.to_string()
+            }
+            None => match self.find_by_path(&instance.path) {
+                Some(name) => name.to_string(),
+                None => self.unique_name(&instance.path),
+            },
+        };
+
+        // a directory is registered only once
+        self.instances
+            .retain(|other, existing| other == &name || existing.path != instance.path);
+        self.instances.insert(name.clone(), instance);
+
+        Ok(name)
+    }
+
+    /// Fails if `name` is taken by a directory other than `path`, which doesn't have to exist
+    /// yet. Lets installs fail before downloading anything.
+    pub fn check_name(&self, name: &str, path: &Path) -> Result<()> {
+        let path = path.canonicalize().or_else(|_| std::path::absolute(path))?;
+        match self.get(name) {
+            Some(existing) if existing.path != path => Err(Error::InvalidArgument(format!(
+                "instance {} already exists at {}",
+                name,
+                existing.path.display()
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Unregisters `name`, returning its instance. The server directory is kept.
+    pub fn remove(&mut self, name: &str) -> Option<Instance> {
+        self.instances.remove(name)
+    }
+
+    /// Directory name, suffixed with a number if it is taken.
+    fn unique_name(&self, path: &Path) -> String {
+        let base = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "server".to_string());
+
+        let mut name = base.clone();
+        let mut n = 2;
+        while self.instances.contains_key(&name) {
+            name = format!("{}-{}", base, n);
+            n += 1;
+        }
+        name
+    }
+}
+
+/// `server-port` from the `server.properties` in `dir`.
+pub fn server_port(dir: &Path) -> Option<u16> {
+    fs::read_to_string(dir.join("server.properties"))
+        .ok()?
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == "server-port")
+        .and_then(|(_, value)| value.trim().parse().ok())
+}
@@ -0,0 +1,288 @@
+//! Declarative server specs in `automc.toml`, applied with `automc apply`.
+
+use crate::cache::Cache;
+use crate::config::ApiConfig;
+use crate::distribution::{
+    eula_accepted, install_eula, install_start_script, start_script, BuildToolsOptions, Channel,
+    Checksum, Distribution, InstallOptions, START_SCRIPT,
+};
+use crate::error::*;
+use crate::http::Http;
+use crate::lock::{Lockfile, LOCKFILE};
+use crate::{latest_build, resolve_version};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the spec file in a server directory.
+pub const SPEC_FILE: &str = "automc.toml";
+
+/// Declarative description of a server, applied with [`apply`].
+///
+/// ```toml
+/// distribution = "paper"
+/// version = "1.21.1"
+/// build = "latest"
+/// java = "/usr/bin/java"
+/// memory = "4G"
+/// eula = true
+///
+/// [properties]
+/// motd = "Hello"
+/// server-port = 25565
+///
+/// [[plugins]]
+/// url = "https://example.com/plugin-1.0.jar"
+/// sha256 = "…"
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Spec {
+    /// Distribution to install.
+    pub distribution: Distribution,
+    /// Version id or constraint, see [`VersionConstraint`](crate::version::VersionConstraint).
+    pub version: String,
+    /// Build id or `latest`.
+    #[serde(default = "latest")]
+    pub build: String,
+    /// Channel used to pick the latest build.
+    #[serde(default)]
+    pub channel: Option<Channel>,
+    /// Java the start script runs, defaults to `java` from the `PATH`.
+    #[serde(default = "java")]
+    pub java: PathBuf,
+    /// Initial and maximum heap size, e.g. `4G`.
+    #[serde(default)]
+    pub memory: Option<String>,
+    /// Has to be `true`, see <https://www.minecraft.net/eula>.
+    #[serde(default)]
+    pub eula: bool,
+    /// Values set in `server.properties`, other values are kept.
+    #[serde(default)]
+    pub properties: BTreeMap<String, toml::Value>,
+    /// Files installed into `plugins/`.
+    #[serde(default)]
+    pub plugins: Vec<Plugin>,
+    /// Files installed into `mods/`.
+    #[serde(default)]
+    pub mods: Vec<Plugin>,
+}
+
+/// A plugin or mod jar.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Plugin {
+    /// Download URL.
+    pub url: String,
+    /// File name, defaults to the last segment of `url`.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Without a checksum, an existing file of the same name is never replaced.
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+fn latest() -> String {
+    "latest".to_string()
+}
+
+fn java() -> PathBuf {
+    "java".into()
+}
+
+impl Spec {
+    /// Reads the spec at `path`, usually an `automc.toml`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+}
+
+/// Converges the server in `dir` to `spec`. Returns a description of every change made,
+/// which is empty if the server already matched.
+///
+/// Files not mentioned in the spec, e.g. other plugins, are left alone.
+pub async fn apply(
+    spec: &Spec,
+    dir: &Path,
+    http: &Http,
+    api: &ApiConfig,
+    cache: Option<Cache>,
+) -> Result<Vec<String>> {
+    if !spec.eula {
+        return Err(Error::InvalidArgument(
+            "the spec has to accept the EULA with `eula = true`".to_string(),
+        ));
+    }
+    let script = start_script(&spec.java, spec.memory.as_deref())?;
+
+    fs::create_dir_all(dir)?;
+    let mut changes = Vec::new();
+
+    if !eula_accepted(dir) {
+        install_eula(dir).await?;
+        changes.push("accepted the EULA".to_string());
+    }
+
+    if fs::read_to_string(dir.join(START_SCRIPT)).ok() != Some(script) {
+        install_start_script(dir, &spec.java, spec.memory.as_deref()).await?;
+        changes.push(format!("wrote {}", START_SCRIPT));
+    }
+
+    let options = InstallOptions {
+        java_path: spec.java.clone(),
+        skip_verify: false,
+        cache,
+        memory: spec.memory.clone(),
+        keep_build_cache: false,
+        build_tools: BuildToolsOptions::default(),
+    };
+    if let Some(change) = apply_server_jar(spec, dir, http, api, &options).await? {
+        changes.push(change);
+    }
+
+    changes.extend(apply_properties(dir, &spec.properties)?);
+
+    for (folder, plugins) in [("plugins", &spec.plugins), ("mods", &spec.mods)] {
+        for plugin in plugins {
+            if let Some(change) = apply_plugin(&dir.join(folder), plugin, http).await? {
+                changes.push(change);
+            }
+        }
+    }
+
+    Ok(changes)
+}
+
+async fn apply_server_jar(
+    spec: &Spec,
+    dir: &Path,
+    http: &Http,
+    api: &ApiConfig,
+    options: &InstallOptions,
+) -> Result<Option<String>> {
+    let provider = spec.distribution.provider(http, api);
+    let version = resolve_version(provider.as_ref(), &spec.version).await?;
+
+    let builds = provider.builds(&version).await?;
+    let build = match spec.build.as_str() {
+        "latest" => latest_build(&builds, spec.channel.unwrap_or(Channel::Default)),
+        build => builds.iter().find(|b| b.id == build),
+    }
+    .ok_or_else(|| Error::InvalidArgument(format!("unknown build {}", spec.build)))?;
+
+    if let Some(mut lock) = Lockfile::load(dir)? {
+        let installed = lock.distribution == spec.distribution
+            && lock.version == version
+            && lock.build == build.id
+            && dir.join("server.jar").exists();
+        if installed {
+            if lock.java_path == spec.java {
+                return Ok(None);
+            }
+            lock.java_path = spec.java.clone();
+            lock.save(dir)?;
+            return Ok(Some(format!(
+                "recorded Java {} in {}",
+                spec.java.display(),
+                LOCKFILE
+            )));
+        }
+    }
+
+    let artifact = provider.resolve(&version, &build.id).await?;
+    provider.install(&artifact, dir, options).await?;
+    Lockfile::new(&artifact, &spec.java).save(dir)?;
+
+    Ok(Some(format!(
+        "installed {} {} build {}",
+        artifact.distribution, artifact.version, artifact.build
+    )))
+}
+
+/// Sets `properties` in `server.properties`, keeping comments, order and other values.
+fn apply_properties(dir: &Path, properties: &BTreeMap<String, toml::Value>) -> Result<Vec<String>> {
+    if properties.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let path = dir.join("server.properties");
+    let content = fs::read_to_string(&path).unwrap_or_default();
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+
+    let mut changes = Vec::new();
+    for (key, value) in properties {
+        let value = match value {
+            toml::Value::String(s) => s.clone(),
+            value => value.to_string(),
+        };
+        let line = format!("{}={}", key, value);
+
+        let existing = lines.iter_mut().find(|l| {
+            !l.trim_start().starts_with('#')
+                && l.split_once('=').is_some_and(|(k, _)| k.trim() == key)
+        });
+        match existing {
+            Some(existing) if existing.split_once('=').map(|(_, v)| v.trim()) == Some(&value) => {}
+            Some(existing) => {
+                *existing = line;
+                changes.push(format!("set {} in server.properties", key));
+            }
+            None => {
+                lines.push(line);
+                changes.push(format!("set {} in server.properties", key));
+            }
+        }
+    }
+
+    if !changes.is_empty() {
+        fs::write(path, lines.join("\n") + "\n")?;
+    }
+    Ok(changes)
+}
+
+async fn apply_plugin(folder: &Path, plugin: &Plugin, http: &Http) -> Result<Option<String>> {
+    let name = match &plugin.name {
+        Some(name) => name.clone(),
+        None => plugin
+            .url
+            .split(['?', '#'])
+            .next()
+            .and_then(|url| url.rsplit('/').next())
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| {
+                Error::InvalidArgument(format!("no file name in {}, set `name`", plugin.url))
+            })?
+            .to_string(),
+    };
+    if name.contains(['/', '\\']) || name == ".." {
+        return Err(Error::InvalidArgument(format!(
+            "invalid file name {}",
+            name
+        )));
+    }
+
+    let path = folder.join(&name);
+    let checksum = plugin.sha256.clone().map(Checksum::Sha256);
+    let folder_name = folder.file_name().unwrap_or_default().to_string_lossy();
+
+    if path.exists() {
+        match &checksum {
+            Some(checksum) if !checksum.matches_file(&path)? => {}
+            _ => return Ok(None),
+        }
+    }
+
+    let existed = path.exists();
+    fs::create_dir_all(folder)?;
+    http.download(&plugin.url, &path, checksum.as_ref(), &name)
+        .await?;
+
+    Ok(Some(format!(
+        "{} {}/{}",
+        if existed { "updated" } else { "installed" },
+        folder_name,
+        name
+    )))
+}
@@ -0,0 +1,498 @@
+//! Parsing and ordering of Minecraft versions and version constraints.
+
+use crate::distribution::Version;
+use crate::error::*;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A parsed Minecraft (or Velocity) version id.
+///
+/// Versions are only comparable within their kind: releases with their pre-releases, weekly
+/// snapshots with each other and pre-Beta versions with each other. Mojang's ids don't say
+/// which release a weekly snapshot leads up to, so e.g. `24w14a` and `1.20.5` are unordered.
+#[derive(Debug, Clone)]
+pub enum MinecraftVersion {
+    /// `1.21.1`, `1.21-pre1`, `1.14 Pre-Release 2`, `26.1-snapshot-1` or `3.4.0-SNAPSHOT`.
+    Release {
+        /// Numeric parts, e.g. `[1, 21, 1]`.
+        parts: Vec<u32>,
+        /// Suffix of a version leading up to the release.
+        pre: Option<PreRelease>,
+    },
+    /// Weekly snapshot like `24w14a`.
+    Snapshot {
+        /// Two-digit year, e.g. `24`.
+        year: u32,
+        /// Week of the year.
+        week: u32,
+        /// Letter telling apart snapshots of the same week, e.g. `a`.
+        suffix: String,
+    },
+    /// `rd-132211`, `inf-20100618`, `c0.30_01c`, `a1.0.4` or `b1.7.3`.
+    Legacy {
+        /// Stage the version belongs to.
+        stage: LegacyStage,
+        /// Numeric parts after the stage prefix, e.g. `[1, 7, 3]` for `b1.7.3`.
+        parts: Vec<u32>,
+    },
+}
+
+/// Suffix of a version leading up to a release.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PreRelease {
+    /// Kind of pre-release.
+    pub kind: PreReleaseKind,
+    /// Number of the pre-release, e.g. `1` for `-pre1`.
+    pub number: u32,
+}
+
+/// Kind of a [`PreRelease`], ordered as they are published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PreReleaseKind {
+    /// `-snapshot-1`, or `-SNAPSHOT` for Velocity.
+    Snapshot,
+    /// `-pre1` or ` Pre-Release 1`.
+    Pre,
+    /// `-rc1`, the release candidate.
+    Rc,
+}
+
+/// Stage of a version before Beta ended, ordered as they were published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LegacyStage {
+    /// `rd-…`
+    PreClassic,
+    /// `inf-…`
+    Indev,
+    /// `c…`
+    Classic,
+    /// `a…`
+    Alpha,
+    /// `b…`
+    Beta,
+}
+
+impl MinecraftVersion {
+    /// Whether the version leads up to a release rather than being one.
+    pub fn is_prerelease(&self) -> bool {
+        !matches!(self, MinecraftVersion::Release { pre: None, .. })
+    }
+
+    /// The release a pre-release leads up to, e.g. `1.21` for `1.21-pre1`.
+    fn release(self) -> Self {
+        match self {
+            MinecraftVersion::Release { parts, .. } => {
+                MinecraftVersion::Release { parts, pre: None }
+            }
+            other => other,
+        }
+    }
+}
+
+impl FromStr for MinecraftVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidArgument(format!("invalid version {}", s));
+
+        if let Some(snapshot) = parse_snapshot(s) {
+            return Ok(snapshot);
+        }
+
+        let legacy = [
+            ("rd-", LegacyStage::PreClassic),
+            ("inf-", LegacyStage::Indev),
+            ("c", LegacyStage::Classic),
+            ("a", LegacyStage::Alpha),
+            ("b", LegacyStage::Beta),
+        ];
+        for (prefix, stage) in legacy {
+            if let Some(rest) = s.strip_prefix(prefix) {
+                if rest.starts_with(|c: char| c.is_ascii_digit()) {
+                    return Ok(MinecraftVersion::Legacy {
+                        stage,
+                        parts: numbers(rest),
+                    });
+                }
+            }
+        }
+
+        // split `1.21-pre1`, `1.14 Pre-Release 2` or `3.4.0-SNAPSHOT` into release and suffix
+        let end = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let (release, suffix) = s.split_at(end);
+        let parts = release
+            .split('.')
+            .map(|part| part.parse::<u32>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let suffix = suffix.trim_start_matches([' ', '-']).to_lowercase();
+        let pre = match suffix.as_str() {
+            "" => None,
+            _ => {
+                let kind = if suffix.starts_with("snapshot") {
+                    PreReleaseKind::Snapshot
+                } else if suffix.starts_with("pre") {
+                    PreReleaseKind::Pre
+                } else if suffix.starts_with("rc") {
+                    PreReleaseKind::Rc
+                } else {
+                    return Err(invalid());
+                };
+                Some(PreRelease {
+                    kind,
+                    number: numbers(&suffix).first().copied().unwrap_or(0),
+                })
+            }
+        };
+
+        Ok(MinecraftVersion::Release { parts, pre })
+    }
+}
+
+/// Equal when the ordering says so, e.g. `1.21` equals `1.21.0`.
+impl PartialEq for MinecraftVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl Eq for MinecraftVersion {}
+
+impl PartialOrd for MinecraftVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        use MinecraftVersion::*;
+
+        match (self, other) {
+            (Release { parts: a, pre: pa }, Release { parts: b, pre: pb }) => {
+                let order = compare_parts(a, b).then_with(|| match (pa, pb) {
+                    (None, None) => Ordering::Equal,
+                    (None, Some(_)) => Ordering::Greater,
+                    (Some(_), None) => Ordering::Less,
+                    (Some(a), Some(b)) => a.cmp(b),
+                });
+                Some(order)
+            }
+            (
+                Snapshot {
+                    year: ya,
+                    week: wa,
+                    suffix: sa,
+                },
+                Snapshot {
+                    year: yb,
+                    week: wb,
+                    suffix: sb,
+                },
+            ) => Some((ya, wa, sa).cmp(&(yb, wb, sb))),
+            (
+                Legacy {
+                    stage: sa,
+                    parts: a,
+                },
+                Legacy {
+                    stage: sb,
+                    parts: b,
+                },
+            ) => Some(sa.cmp(sb).then_with(|| compare_parts(a, b))),
+            // everything before Beta is older than any release or snapshot
+            (Legacy { .. }, _) => Some(Ordering::Less),
+            (_, Legacy { .. }) => Some(Ordering::Greater),
+            _ => None,
+        }
+    }
+}
+
+/// Compares numeric parts, treating missing parts as zero (`1.21` == `1.21.0`).
+fn compare_parts(a: &[u32], b: &[u32]) -> Ordering {
+    let len = a.len().max(b.len());
+    (0..len)
+        .map(|i| {
+            let a = a.get(i).copied().unwrap_or(0);
+            let b = b.get(i).copied().unwrap_or(0);
+            a.cmp(&b)
+        })
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// `24w14a` style snapshot ids.
+fn parse_snapshot(s: &str) -> Option<MinecraftVersion> {
+    let (year, rest) = s.split_once('w')?;
+    if year.len() != 2 || rest.len() < 2 {
+        return None;
+    }
+    let (week, suffix) = rest.split_at(2);
+
+    Some(MinecraftVersion::Snapshot {
+        year: year.parse().ok()?,
+        week: week.parse().ok()?,
+        suffix: suffix.to_string(),
+    })
+}
+
+/// All runs of digits in `s`.
+fn numbers(s: &str) -> Vec<u32> {
+    s.split(|c: char| !c.is_ascii_digit())
+        .filter_map(|n| n.parse().ok())
+        .collect()
+}
+
+/// Selects a version from a distribution's version list.
+///
+/// Apart from exact ids and `latest-snapshot`, constraints only match versions the
+/// distribution marks as stable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionConstraint {
+    /// `latest`: the newest stable version.
+    Latest,
+    /// `latest-release`: the newest stable version, like `latest`.
+    LatestRelease,
+    /// `latest-snapshot`: the newest version, stable or not.
+    LatestSnapshot,
+    /// `1.21.x` or `1.21.*`: the newest stable version starting with these parts.
+    Wildcard(Vec<u32>),
+    /// `>=1.20.4,<1.21`: the newest stable version matching all comparisons.
+    Range(Vec<(Comparison, MinecraftVersion)>),
+    /// A version id.
+    Exact(String),
+}
+
+/// Comparison operator of a version range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+    /// `<`
+    Less,
+    /// `<=`
+    LessOrEqual,
+    /// `=`
+    Equal,
+}
+
+impl FromStr for VersionConstraint {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        match s {
+            "latest" => return Ok(VersionConstraint::Latest),
+            "latest-release" => return Ok(VersionConstraint::LatestRelease),
+            "latest-snapshot" => return Ok(VersionConstraint::LatestSnapshot),
+            _ => {}
+        }
+
+        if let Some(prefix) = s.strip_suffix(".x").or_else(|| s.strip_suffix(".*")) {
+            let parts = prefix
+                .split('.')
+                .map(|part| part.parse::<u32>())
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|_| Error::InvalidArgument(format!("invalid version pattern {}", s)))?;
+            return Ok(VersionConstraint::Wildcard(parts));
+        }
+
+        if s.starts_with(['<', '>', '=']) {
+            let comparisons = s
+                .split(',')
+                .map(|comparison| {
+                    let comparison = comparison.trim();
+                    let (op, version) = [
+                        (">=", Comparison::GreaterOrEqual),
+                        ("<=", Comparison::LessOrEqual),
+                        (">", Comparison::Greater),
+                        ("<", Comparison::Less),
+                        ("=", Comparison::Equal),
+                    ]
+                    .into_iter()
+                    .find_map(|(prefix, op)| Some((op, comparison.strip_prefix(prefix)?)))
+                    .ok_or_else(|| {
+                        Error::InvalidArgument(format!("invalid comparison {}", comparison))
+                    })?;
+                    Ok((op, version.trim().parse()?))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(VersionConstraint::Range(comparisons));
+        }
+
+        Ok(VersionConstraint::Exact(s.to_string()))
+    }
+}
+
+impl Display for VersionConstraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VersionConstraint::Latest => write!(f, "latest"),
+            VersionConstraint::LatestRelease => write!(f, "latest-release"),
+            VersionConstraint::LatestSnapshot => write!(f, "latest-snapshot"),
+            VersionConstraint::Wildcard(parts) => {
+                for part in parts {
+                    write!(f, "{}.", part)?;
+                }
+                write!(f, "x")
+            }
+            VersionConstraint::Range(_) => write!(f, "version range"),
+            VersionConstraint::Exact(id) => write!(f, "{}", id),
+        }
+    }
+}
+
+impl VersionConstraint {
+    /// The best matching version of `versions`, which are sorted newest first.
+    pub fn select<'a>(&self, versions: &'a [Version]) -> Option<&'a Version> {
+        match self {
+            VersionConstraint::Latest | VersionConstraint::LatestRelease => {
+                versions.iter().find(|v| v.stable)
+            }
+            VersionConstraint::LatestSnapshot => versions.first(),
+            VersionConstraint::Exact(id) => versions.iter().find(|v| &v.id == id),
+            VersionConstraint::Wildcard(_) | VersionConstraint::Range(_) => versions
+                .iter()
+                .filter(|v| v.stable)
+                // a suffix on a stable version, like Velocity's `-SNAPSHOT`, doesn't make it a
+                // pre-release
+                .filter_map(|v| Some((v, v.id.parse::<MinecraftVersion>().ok()?.release())))
+                .filter(|(_, parsed)| self.matches(parsed))
+                .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                .map(|(v, _)| v),
+        }
+    }
+
+    fn matches(&self, version: &MinecraftVersion) -> bool {
+        match self {
+            VersionConstraint::Wildcard(prefix) => match version {
+                MinecraftVersion::Release { parts, pre: None } => {
+                    parts.len() >= prefix.len() && parts.starts_with(prefix)
+                        || parts.len() < prefix.len()
+                            && compare_parts(parts, prefix) == Ordering::Equal
+                }
+                _ => false,
+            },
+            VersionConstraint::Range(comparisons) => comparisons.iter().all(|(op, bound)| {
+                let Some(order) = version.partial_cmp(bound) else {
+                    return false;
+                };
+                match op {
+                    Comparison::Greater => order.is_gt(),
+                    Comparison::GreaterOrEqual => order.is_ge(),
+                    Comparison::Less => order.is_lt(),
+                    Comparison::LessOrEqual => order.is_le(),
+                    Comparison::Equal => order.is_eq(),
+                }
+            }),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(id: &str) -> MinecraftVersion {
+        id.parse().unwrap()
+    }
+
+    fn versions(ids: &[(&str, bool)]) -> Vec<Version> {
+        ids.iter()
+            .map(|(id, stable)| Version {
+                id: id.to_string(),
+                stable: *stable,
+                support: None,
+            })
+            .collect()
+    }
+
+    fn select(constraint: &str, versions: &[Version]) -> Option<String> {
+        let constraint: VersionConstraint = constraint.parse().unwrap();
+        constraint.select(versions).map(|v| v.id.clone())
+    }
+
+    #[test]
+    fn releases() {
+        assert!(v("1.21.1") > v("1.21"));
+        assert!(v("1.21") > v("1.20.6"));
+        assert!(v("1.10") > v("1.9.4"));
+        assert_eq!(v("1.21"), v("1.21.0"));
+        assert_eq!(v("1.21").partial_cmp(&v("1.21.0")), Some(Ordering::Equal));
+    }
+
+    #[test]
+    fn pre_releases() {
+        assert!(v("1.21-pre1") < v("1.21-pre2"));
+        assert!(v("1.21-pre2") < v("1.21-rc1"));
+        assert!(v("1.21-rc1") < v("1.21"));
+        assert!(v("1.20.6") < v("1.21-pre1"));
+        assert_eq!(v("1.14 Pre-Release 2"), v("1.14-pre2"));
+        assert!(v("26.1-snapshot-1") < v("26.1-pre1"));
+        assert!(v("1.21-pre1").is_prerelease());
+        assert!(!v("1.21").is_prerelease());
+    }
+
+    #[test]
+    fn snapshots() {
+        assert!(v("24w14a") < v("24w14b"));
+        assert!(v("24w14a") < v("24w18a"));
+        assert!(v("23w51b") < v("24w03a"));
+        assert_eq!(v("24w14a").partial_cmp(&v("1.20.5")), None);
+        assert!(v("24w14a").is_prerelease());
+    }
+
+    #[test]
+    fn legacy() {
+        assert!(v("a1.0.4") < v("b1.7.3"));
+        assert!(v("b1.7.3") < v("b1.8"));
+        assert!(v("rd-132211") < v("c0.30_01c"));
+        assert!(v("b1.7.3") < v("1.0"));
+        assert!(v("a1.0.4") < v("24w14a"));
+    }
+
+    #[test]
+    fn wildcard() {
+        let list = versions(&[
+            ("1.21.1", true),
+            ("1.21", true),
+            ("1.20.6", true),
+            ("1.20.5-rc1", false),
+            ("1.20.4", true),
+        ]);
+        assert_eq!(select("1.21.x", &list).as_deref(), Some("1.21.1"));
+        assert_eq!(select("1.20.*", &list).as_deref(), Some("1.20.6"));
+        assert_eq!(select("1.19.x", &list), None);
+    }
+
+    #[test]
+    fn range() {
+        let list = versions(&[
+            ("1.21.1", true),
+            ("1.21", true),
+            ("1.21-pre1", false),
+            ("1.20.6", true),
+            ("1.20.4", true),
+            ("1.20.2", true),
+        ]);
+        assert_eq!(select(">=1.20.4,<1.21", &list).as_deref(), Some("1.20.6"));
+        assert_eq!(select("<1.20.4", &list).as_deref(), Some("1.20.2"));
+        assert_eq!(select(">1.21.1", &list), None);
+    }
+
+    #[test]
+    fn latest() {
+        let list = versions(&[("24w14a", false), ("1.20.4", true)]);
+        assert_eq!(select("latest", &list).as_deref(), Some("1.20.4"));
+        assert_eq!(select("latest-snapshot", &list).as_deref(), Some("24w14a"));
+    }
+
+    #[test]
+    fn stable_snapshot_versions() {
+        // Velocity marks its `-SNAPSHOT` versions as stable
+        let list = versions(&[("3.4.0-SNAPSHOT", true), ("3.3.0-SNAPSHOT", true)]);
+        assert_eq!(select("latest", &list).as_deref(), Some("3.4.0-SNAPSHOT"));
+        assert_eq!(select("3.x", &list).as_deref(), Some("3.4.0-SNAPSHOT"));
+        assert_eq!(select("<3.4", &list).as_deref(), Some("3.3.0-SNAPSHOT"));
+    }
+}